@@ -1,4 +1,7 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::config::DatabaseEngine;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -16,8 +19,45 @@ pub struct Cli {
 pub enum Commands {
     /// Connect to a database
     Connect {
-        /// Database alias
-        alias: String,
+        /// Database alias; opens an interactive picker when omitted
+        alias: Option<String>,
+
+        /// Skip the confirmation prompt for protected databases
+        #[arg(
+            short,
+            long,
+            env = "DBJUMP_ASSUME_YES",
+            value_parser = clap::builder::FalseyValueParser::new()
+        )]
+        yes: bool,
+
+        /// Open the session in read-only mode
+        #[arg(long)]
+        read_only: bool,
+
+        /// Record the session transcript
+        #[arg(long)]
+        record: bool,
+
+        /// Print the command instead of running it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Extra arguments to pass to the database CLI tool
+        #[arg(trailing_var_arg = true)]
+        extra_args: Vec<String>,
+    },
+
+    /// Reconnect to the most recently used database
+    Last {
+        /// Skip the confirmation prompt for protected databases
+        #[arg(
+            short,
+            long,
+            env = "DBJUMP_ASSUME_YES",
+            value_parser = clap::builder::FalseyValueParser::new()
+        )]
+        yes: bool,
 
         /// Extra arguments to pass to the database CLI tool
         #[arg(trailing_var_arg = true)]
@@ -36,6 +76,22 @@ pub enum Commands {
         /// Output format
         #[arg(short, long, value_name = "FORMAT", default_value = "text")]
         format: ListFormat,
+
+        /// Only show databases with this tag (repeat to require several)
+        #[arg(short, long, value_name = "TAG")]
+        tag: Vec<String>,
+
+        /// Only show databases using this engine
+        #[arg(short, long, value_name = "ENGINE")]
+        engine: Option<DatabaseEngine>,
+
+        /// Only show databases in this environment
+        #[arg(long, value_name = "ENV")]
+        environment: Option<String>,
+
+        /// Sort order
+        #[arg(short, long, value_name = "ORDER", default_value = "config")]
+        sort: ListSort,
     },
 
     /// Show connection information for a database
@@ -44,8 +100,150 @@ pub enum Commands {
         alias: String,
     },
 
+    /// Print the command `connect` would run, with the password masked
+    ShowCommand {
+        /// Database alias
+        alias: String,
+
+        /// Extra arguments to pass to the database CLI tool
+        #[arg(trailing_var_arg = true)]
+        extra_args: Vec<String>,
+    },
+
     /// Validate configuration file
-    Validate,
+    Validate {
+        /// Output format
+        #[arg(short, long, value_name = "FORMAT", default_value = "text")]
+        format: ValidateFormat,
+
+        /// Fail on warnings as well as errors
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Add a database to the configuration
+    Add {
+        /// Database alias
+        alias: String,
+
+        /// Database engine
+        #[arg(short, long)]
+        engine: DatabaseEngine,
+
+        /// Server host
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Unix domain socket (the directory for PostgreSQL)
+        #[arg(long, conflicts_with = "host")]
+        socket: Option<String>,
+
+        /// Server port
+        #[arg(short, long)]
+        port: Option<u16>,
+
+        /// User name
+        #[arg(short, long)]
+        user: Option<String>,
+
+        /// Prompt for a password and store it in the file
+        #[arg(long)]
+        ask_password: bool,
+
+        /// Database name
+        #[arg(short, long)]
+        database: Option<String>,
+
+        /// Tag (repeat for several)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Deployment environment, e.g. prod or staging
+        #[arg(long)]
+        environment: Option<String>,
+
+        /// Template or alias to inherit unset fields from
+        #[arg(long)]
+        extends: Option<String>,
+
+        /// Extra option for the CLI tool (repeat for several)
+        #[arg(long = "option", value_name = "OPTION", allow_hyphen_values = true)]
+        options: Vec<String>,
+
+        /// File to add the alias to [default: the global config file]
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+
+    /// Edit the configuration in $EDITOR, validating before it is saved
+    Edit {
+        /// Edit the file that defines this alias [default: the global config file]
+        alias: Option<String>,
+    },
+
+    /// Remove a database from the file that defines it
+    Remove {
+        /// Database alias
+        alias: String,
+    },
+
+    /// Rename a database alias
+    Rename {
+        /// Current alias
+        alias: String,
+
+        /// New alias
+        new_alias: String,
+    },
+
+    /// Copy a database entry under a new alias
+    Copy {
+        /// Alias to copy
+        alias: String,
+
+        /// Alias for the copy
+        new_alias: String,
+    },
+
+    /// Show the connection audit log
+    Log {
+        /// Only show connections to this alias
+        #[arg(short, long)]
+        alias: Option<String>,
+
+        /// Only show connections since a time: 30m, 12h, 7d, 2w or YYYY-MM-DD
+        #[arg(short, long, value_name = "WHEN")]
+        since: Option<String>,
+    },
+
+    /// Replay a recorded session, or list sessions when none is given
+    Replay {
+        /// Session file name or path
+        session: Option<String>,
+
+        /// Playback speed multiplier
+        #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+        speed: f64,
+    },
+
+    /// Generate a pg_service.conf from the PostgreSQL aliases
+    PgService {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Trust a project-local .dbjump.toml so its aliases are loaded
+    Trust {
+        /// Project config file (defaults to the nearest .dbjump.toml)
+        path: Option<PathBuf>,
+    },
+
+    /// Revoke trust for a project-local .dbjump.toml
+    Untrust {
+        /// Project config file (defaults to the nearest .dbjump.toml)
+        path: Option<PathBuf>,
+    },
 
     /// Generate shell completions
     Completions {
@@ -69,6 +267,38 @@ pub enum Commands {
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum ListFormat {
     Text,
+    /// Alias, engine, environment, tags and the file it was loaded from
+    Long,
+    Json,
+}
+
+/// A positive, finite playback speed
+fn parse_speed(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        Ok(_) => Err("speed must be greater than 0".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+pub enum ListSort {
+    /// Order of appearance in the configuration files
+    Config,
+    Alias,
+    /// Group by environment, aliases without one last
+    Environment,
+    /// Most frequently and recently used first
+    Frecent,
+    /// Group by environment, most used first within each group
+    EnvironmentFrecent,
+}
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+pub enum ValidateFormat {
+    /// Compiler-style messages
+    Text,
+    /// A JSON array of diagnostics
     Json,
 }
 
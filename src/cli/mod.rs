@@ -1,5 +1,7 @@
 pub mod args;
 pub mod completions;
+pub mod picker;
+pub mod prompt;
 pub mod shell;
 
 pub use args::{Cli, Commands};
@@ -0,0 +1,322 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write};
+
+use crate::config::{DatabaseConfig, DatabaseEngine};
+use crate::database::get_connector;
+use crate::error::Result;
+
+const PROMPT: &str = "Connect to > ";
+/// Rows used above the list: prompt, facets and separator
+const HEADER_ROWS: u16 = 3;
+
+/// What the picker should do after a key press
+#[derive(Debug, PartialEq)]
+enum Action {
+    Continue,
+    Select,
+    Cancel,
+}
+
+/// Built-in fuzzy finder over the configured aliases, used when fzf isn't
+/// available. Draws on stderr so stdout stays clean.
+pub struct Picker<'a> {
+    databases: Vec<&'a DatabaseConfig>,
+    engines: Vec<DatabaseEngine>,
+    tags: Vec<String>,
+    query: String,
+    /// Index into `engines`, or None for all engines
+    engine: Option<usize>,
+    /// Index into `tags`, or None for all tags
+    tag: Option<usize>,
+    matches: Vec<&'a DatabaseConfig>,
+    selected: usize,
+}
+
+impl<'a> Picker<'a> {
+    /// `databases` should already be in the order to show them when the
+    /// query is empty
+    pub fn new(databases: Vec<&'a DatabaseConfig>) -> Self {
+        let mut engines = Vec::new();
+        let mut tags = Vec::new();
+        for db in &databases {
+            if !engines.contains(&db.engine) {
+                engines.push(db.engine.clone());
+            }
+            for tag in &db.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        tags.sort();
+
+        let mut picker = Picker {
+            databases,
+            engines,
+            tags,
+            query: String::new(),
+            engine: None,
+            tag: None,
+            matches: Vec::new(),
+            selected: 0,
+        };
+        picker.update_matches();
+        picker
+    }
+
+    /// Run the picker on the terminal; None if the user cancelled
+    pub fn run(mut self) -> Result<Option<&'a DatabaseConfig>> {
+        let _terminal = TerminalGuard::enable()?;
+        let mut stderr = io::stderr();
+
+        loop {
+            self.render(&mut stderr)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match self.handle_key(key) {
+                Action::Continue => {}
+                Action::Select => return Ok(self.matches.get(self.selected).copied()),
+                Action::Cancel => return Ok(None),
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter if !self.matches.is_empty() => return Action::Select,
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Char('c') | KeyCode::Char('g') if ctrl => return Action::Cancel,
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.move_selection(1),
+            KeyCode::Char('e') if ctrl => {
+                self.engine = cycle(self.engine, self.engines.len());
+                self.update_matches();
+            }
+            KeyCode::Char('t') if ctrl => {
+                self.tag = cycle(self.tag, self.tags.len());
+                self.update_matches();
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.update_matches();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update_matches();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.update_matches();
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let last = self.matches.len() - 1;
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    fn update_matches(&mut self) {
+        let engine = self.engine.map(|i| &self.engines[i]);
+        let tag = self.tag.map(|i| self.tags[i].as_str());
+        let query = self.query.to_lowercase();
+
+        let mut ranked: Vec<(u8, &'a DatabaseConfig)> = self
+            .databases
+            .iter()
+            .filter(|db| engine.is_none_or(|e| &db.engine == e))
+            .filter(|db| tag.is_none_or(|t| db.has_tag(t)))
+            .filter_map(|db| match_rank(&db.alias.to_lowercase(), &query).map(|rank| (rank, *db)))
+            .collect();
+        // Stable, so equally good matches keep their original order
+        ranked.sort_by_key(|(rank, _)| *rank);
+
+        self.matches = ranked.into_iter().map(|(_, db)| db).collect();
+        self.selected = 0;
+    }
+
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        // Some ptys report no size at all
+        let (width, height) = match terminal::size()? {
+            (0, _) | (_, 0) => (80, 24),
+            size => size,
+        };
+        let (width, height) = (width as usize, height.max(HEADER_ROWS + 1));
+        let list_rows = (height - HEADER_ROWS) as usize;
+        let list_width = (width / 2).max(1);
+        let preview_width = width.saturating_sub(list_width + 3);
+
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+
+        let facets = format!(
+            "engine: {}  tag: {}  {}/{}  (Ctrl-E engine, Ctrl-T tag, Esc quit)",
+            self.engine
+                .map_or("all".to_string(), |i| format!("{:?}", self.engines[i])),
+            self.tag.map_or("all", |i| self.tags[i].as_str()),
+            self.matches.len(),
+            self.databases.len(),
+        );
+        queue!(
+            out,
+            cursor::MoveTo(0, 1),
+            Print(truncate(&facets, width)),
+            cursor::MoveTo(0, 2),
+            Print("─".repeat(width)),
+        )?;
+
+        // Keep the selected row on screen
+        let offset = (self.selected + 1).saturating_sub(list_rows);
+        for (row, db) in self.matches.iter().skip(offset).take(list_rows).enumerate() {
+            let line = format!("{:<w$}", truncate(&db.alias, list_width), w = list_width);
+            queue!(out, cursor::MoveTo(0, HEADER_ROWS + row as u16))?;
+            if offset + row == self.selected {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(line),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(out, Print(line))?;
+            }
+        }
+
+        if let Some(db) = self.matches.get(self.selected) {
+            let preview = get_connector(&db.engine).format_preview(db);
+            for (row, line) in preview.lines().take(list_rows).enumerate() {
+                queue!(
+                    out,
+                    cursor::MoveTo((list_width + 1) as u16, HEADER_ROWS + row as u16),
+                    Print("│ "),
+                    Print(truncate(line, preview_width)),
+                )?;
+            }
+        }
+
+        let prompt = format!("{}{}", PROMPT, self.query);
+        queue!(out, cursor::MoveTo(0, 0), Print(truncate(&prompt, width)))?;
+        out.flush()
+    }
+}
+
+/// How well `alias` matches `query`: prefix, then substring, then
+/// subsequence; None if it doesn't match at all
+fn match_rank(alias: &str, query: &str) -> Option<u8> {
+    if alias.starts_with(query) {
+        Some(0)
+    } else if alias.contains(query) {
+        Some(1)
+    } else {
+        let mut chars = alias.chars();
+        query.chars().all(|q| chars.any(|c| c == q)).then_some(2)
+    }
+}
+
+/// Step through None, Some(0), .., Some(len - 1) and back to None
+fn cycle(current: Option<usize>, len: usize) -> Option<usize> {
+    match current {
+        None if len > 0 => Some(0),
+        Some(i) if i + 1 < len => Some(i + 1),
+        _ => None,
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+/// Puts stderr in raw mode on the alternate screen until dropped
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(e) = execute!(io::stderr(), terminal::EnterAlternateScreen) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e);
+        }
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(alias: &str, engine: DatabaseEngine, tags: &[&str]) -> DatabaseConfig {
+        DatabaseConfig {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..DatabaseConfig::new(alias, engine)
+        }
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn aliases(picker: &Picker) -> Vec<String> {
+        picker.matches.iter().map(|db| db.alias.clone()).collect()
+    }
+
+    #[test]
+    fn test_filter_ranks_prefix_before_substring() {
+        let databases = [
+            database("staging-pg", DatabaseEngine::PostgreSQL, &[]),
+            database("pg-prod", DatabaseEngine::PostgreSQL, &["prod"]),
+            database("prod-ch", DatabaseEngine::ClickHouse, &["prod"]),
+        ];
+        let mut picker = Picker::new(databases.iter().collect());
+
+        for c in "pg".chars() {
+            picker.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        assert_eq!(aliases(&picker), vec!["pg-prod", "staging-pg"]);
+
+        picker.handle_key(key(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(picker.matches.len(), 3);
+    }
+
+    #[test]
+    fn test_facets_narrow_the_list() {
+        let databases = [
+            database("staging-pg", DatabaseEngine::PostgreSQL, &[]),
+            database("pg-prod", DatabaseEngine::PostgreSQL, &["prod"]),
+            database("prod-ch", DatabaseEngine::ClickHouse, &["prod"]),
+        ];
+        let mut picker = Picker::new(databases.iter().collect());
+
+        picker.handle_key(key(KeyCode::Char('t'), KeyModifiers::CONTROL));
+        assert_eq!(aliases(&picker), vec!["pg-prod", "prod-ch"]);
+
+        picker.handle_key(key(KeyCode::Char('e'), KeyModifiers::CONTROL));
+        assert_eq!(aliases(&picker), vec!["pg-prod"]);
+
+        picker.handle_key(key(KeyCode::Down, KeyModifiers::NONE));
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Action::Select
+        );
+        assert_eq!(picker.matches[picker.selected].alias, "pg-prod");
+    }
+}
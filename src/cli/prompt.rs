@@ -0,0 +1,96 @@
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::config::DatabaseConfig;
+use crate::error::{DbJumpError, Result};
+
+/// Whether we can ask the user a question: prompts go to stderr and
+/// answers come from stdin, so both must be attached to a terminal.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Print a prompt to stderr and read one line from stdin
+pub fn read_line(prompt: &str) -> Result<String> {
+    let mut stderr = io::stderr();
+    write!(stderr, "{}", prompt)?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Read a line from the terminal without echoing it
+pub fn read_password(prompt: &str) -> Result<String> {
+    if !is_interactive() {
+        return Err(DbJumpError::ConfigError(
+            "Cannot prompt for a password when not running in a terminal".to_string(),
+        ));
+    }
+
+    let mut stderr = io::stderr();
+    write!(stderr, "{}", prompt)?;
+    stderr.flush()?;
+
+    terminal::enable_raw_mode()?;
+    let password = read_hidden_line();
+    let _ = terminal::disable_raw_mode();
+    writeln!(stderr)?;
+
+    Ok(password?)
+}
+
+fn read_hidden_line() -> io::Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+}
+
+/// Ask a y/N question; anything other than "y" or "yes" means no
+pub fn confirm(question: &str) -> Result<bool> {
+    let answer = read_line(&format!("{} [y/N] ", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Make the user type the alias name before connecting to a protected database
+pub fn confirm_connection(config: &DatabaseConfig) -> Result<()> {
+    if !is_interactive() {
+        return Err(DbJumpError::ConfirmationRequired(config.alias.clone()));
+    }
+
+    let environment = config
+        .environment
+        .as_deref()
+        .map(|env| format!(" ({})", env.to_uppercase()))
+        .unwrap_or_default();
+    eprintln!(
+        "You are about to connect to '{}'{}.",
+        config.alias, environment
+    );
+
+    let answer = read_line("Type the alias name to continue: ")?;
+    if answer != config.alias {
+        return Err(DbJumpError::ConnectionAborted(config.alias.clone()));
+    }
+
+    Ok(())
+}
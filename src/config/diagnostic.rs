@@ -0,0 +1,282 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml_edit::{ImDocument, Item};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in the configuration, with where it was found when known
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub alias: Option<String>,
+    pub field: Option<String>,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Text of the offending line and the width to underline, for rendering
+    #[serde(skip)]
+    snippet: Option<(String, usize)>,
+    /// Which entry to point at when a file defines the alias more than once
+    #[serde(skip)]
+    occurrence: usize,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Diagnostic {
+            severity,
+            message,
+            alias: None,
+            field: None,
+            file: None,
+            line: None,
+            column: None,
+            snippet: None,
+            occurrence: 0,
+        }
+    }
+
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    pub fn field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    /// Point at the nth definition of the alias in its file, counting from 0
+    pub fn occurrence(mut self, occurrence: usize) -> Self {
+        self.occurrence = occurrence;
+        self
+    }
+
+    pub fn file(mut self, file: Option<&Path>) -> Self {
+        self.file = file.map(Path::to_path_buf);
+        self
+    }
+
+    /// Point at a byte range of `content`, the text of `file`
+    pub fn span(mut self, content: &str, span: Range<usize>) -> Self {
+        let start = span.start.min(content.len());
+        let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[start..]
+            .find('\n')
+            .map_or(content.len(), |i| start + i);
+        let line_text = content[line_start..line_end].trim_end_matches('\r');
+
+        let column = content[line_start..start].chars().count();
+        let width = content[start..span.end.clamp(start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        self.line = Some(content[..start].matches('\n').count() + 1);
+        self.column = Some(column + 1);
+        self.snippet = Some((line_text.to_string(), width));
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Render like a compiler message, with the offending line when known
+    pub fn render(&self) -> String {
+        let mut out = format!("{}: {}", self.severity, self.message);
+
+        let Some(file) = &self.file else {
+            if let Some(alias) = &self.alias {
+                out.push_str(&format!("\n  = alias: {}", alias));
+            }
+            return out;
+        };
+
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                out.push_str(&format!("\n  --> {}:{}:{}", file.display(), line, column))
+            }
+            _ => out.push_str(&format!("\n  --> {}", file.display())),
+        }
+
+        if let (Some(line), Some(column), Some((text, width))) =
+            (self.line, self.column, &self.snippet)
+        {
+            let gutter = " ".repeat(line.to_string().len());
+            out.push_str(&format!(
+                "\n{gutter} |\n{line} | {text}\n{gutter} | {}{}",
+                " ".repeat(column - 1),
+                "^".repeat(*width),
+            ));
+        }
+
+        out
+    }
+}
+
+/// Fill in line and column for diagnostics that name a file and an alias,
+/// pointing at the field if there is one and at the alias otherwise.
+/// `pending` is an edit not yet written to disk, keyed by canonical path,
+/// searched in place of that file.
+pub fn locate(diagnostics: &mut [Diagnostic], pending: Option<(&Path, &str)>) {
+    let mut documents: HashMap<PathBuf, Option<ImDocument<String>>> = HashMap::new();
+
+    for diagnostic in diagnostics.iter_mut() {
+        if diagnostic.line.is_some() {
+            continue;
+        }
+        let (Some(file), Some(alias)) = (&diagnostic.file, &diagnostic.alias) else {
+            continue;
+        };
+
+        let document = documents.entry(file.clone()).or_insert_with(|| {
+            let key = fs::canonicalize(file).unwrap_or_else(|_| file.clone());
+            let content = match pending {
+                Some((pending_path, content)) if pending_path == key => Some(content.to_string()),
+                _ => fs::read_to_string(file).ok(),
+            };
+            content.and_then(|content| ImDocument::parse(content).ok())
+        });
+        let Some(document) = document else {
+            continue;
+        };
+
+        let field = diagnostic.field.as_deref();
+        if let Some(span) = find_span(document, alias, field, diagnostic.occurrence) {
+            let located = diagnostic.clone().span(document.raw(), span);
+            *diagnostic = located;
+        }
+    }
+}
+
+/// Span of `field` (a dotted path) in the `[[database]]` or `[[template]]`
+/// entry named `alias`, falling back to the entry's name
+fn find_span(
+    document: &ImDocument<String>,
+    alias: &str,
+    field: Option<&str>,
+    occurrence: usize,
+) -> Option<Range<usize>> {
+    let entry = [("database", "alias"), ("template", "name")]
+        .iter()
+        .find_map(|(key, name_key)| {
+            document
+                .get(key)
+                .and_then(Item::as_array_of_tables)?
+                .iter()
+                .filter(|table| table.get(name_key).and_then(Item::as_str) == Some(alias))
+                .nth(occurrence)
+                .map(|table| (table, *name_key))
+        });
+    let (table, name_key) = entry?;
+
+    if let Some(field) = field {
+        let mut parts = field.split('.');
+        let mut item = parts.next().and_then(|part| table.get(part));
+        for part in parts {
+            item = item
+                .and_then(Item::as_table_like)
+                .and_then(|nested| nested.get(part));
+        }
+        if let Some(span) = item.and_then(Item::span) {
+            return Some(span);
+        }
+    }
+
+    table.get(name_key).and_then(Item::span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_with_snippet() {
+        let content = "[[database]]\nalias = \"bad alias\"\n";
+        let diagnostic = Diagnostic::error("invalid alias")
+            .file(Some(Path::new("config.toml")))
+            .span(content, 21..32);
+
+        assert_eq!(
+            diagnostic.render(),
+            "error: invalid alias\n  --> config.toml:2:9\n  |\n2 | alias = \"bad alias\"\n  |         ^^^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_locate_field_in_alias() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[[database]]\nalias = \"a\"\nport = 1\n\n[[database]]\nalias = \"b\"\nport = 0\n",
+        )
+        .unwrap();
+
+        let mut diagnostics = vec![
+            Diagnostic::error("bad port")
+                .alias("b")
+                .field("port")
+                .file(Some(&path)),
+            Diagnostic::warning("no field").alias("a").file(Some(&path)),
+        ];
+        locate(&mut diagnostics, None);
+
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(7), Some(8))
+        );
+        assert_eq!(
+            (diagnostics[1].line, diagnostics[1].column),
+            (Some(2), Some(9))
+        );
+    }
+
+    #[test]
+    fn test_locate_in_pending_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[[database]]\nalias = \"b\"\n").unwrap();
+        let pending = "# edited\n\n[[database]]\nalias = \"b\"\nport = 0\n";
+
+        let mut diagnostics = vec![Diagnostic::error("bad port")
+            .alias("b")
+            .field("port")
+            .file(Some(&path))];
+        let key = fs::canonicalize(&path).unwrap();
+        locate(&mut diagnostics, Some((&key, pending)));
+
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(5), Some(8))
+        );
+    }
+}
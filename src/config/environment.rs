@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// Settings for an `[environments.<name>]` table
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EnvironmentConfig {
+    /// Color of the environment tag in the client prompt
+    #[serde(default)]
+    pub color: Option<PromptColor>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl PromptColor {
+    /// ANSI SGR foreground code
+    pub fn ansi_code(self) -> u8 {
+        match self {
+            PromptColor::Black => 30,
+            PromptColor::Red => 31,
+            PromptColor::Green => 32,
+            PromptColor::Yellow => 33,
+            PromptColor::Blue => 34,
+            PromptColor::Magenta => 35,
+            PromptColor::Cyan => 36,
+            PromptColor::White => 37,
+        }
+    }
+
+    /// Color used when an environment has no `[environments.<name>]` color
+    pub fn default_for(environment: &str) -> Option<Self> {
+        match environment {
+            _ if is_prod_environment(environment) => Some(PromptColor::Red),
+            "staging" => Some(PromptColor::Yellow),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `environment` names production, which implies confirmation and
+/// the stricter security checks
+pub fn is_prod_environment(environment: &str) -> bool {
+    matches!(environment, "prod" | "production")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_environment_color() {
+        let env: EnvironmentConfig = toml::from_str("color = \"magenta\"").unwrap();
+        assert_eq!(env.color, Some(PromptColor::Magenta));
+        assert!(toml::from_str::<EnvironmentConfig>("color = \"pink\"").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How to pick among the endpoints in `hosts`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostStrategy {
+    /// Probe each endpoint over TCP in order and connect to the first that answers
+    #[default]
+    FirstReachable,
+    /// Connect to an endpoint chosen at random
+    Random,
+    /// Hand every endpoint to the client and let it fail over itself
+    EngineNative,
+}
+
+impl fmt::Display for HostStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostStrategy::FirstReachable => write!(f, "first-reachable"),
+            HostStrategy::Random => write!(f, "random"),
+            HostStrategy::EngineNative => write!(f, "engine-native"),
+        }
+    }
+}
+
+/// One `host[:port]` entry of `hosts`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostAddress {
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl HostAddress {
+    /// Parse `host`, `host:port`, `[v6]` or `[v6]:port`
+    pub fn parse(entry: &str) -> Result<Self, String> {
+        let (host, port) = if let Some(rest) = entry.strip_prefix('[') {
+            let (host, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| format!("'{}' is missing a closing ']'", entry))?;
+            match rest {
+                "" => (host, None),
+                _ => {
+                    let port = rest
+                        .strip_prefix(':')
+                        .ok_or_else(|| format!("'{}' has text after ']'", entry))?;
+                    (host, Some(port))
+                }
+            }
+        } else {
+            match entry.rsplit_once(':') {
+                // More than one colon is a bare IPv6 address
+                Some((host, port)) if !host.contains(':') => (host, Some(port)),
+                _ => (entry, None),
+            }
+        };
+
+        if host.is_empty() {
+            return Err(format!("'{}' has no host", entry));
+        }
+        let port = match port {
+            Some(port) => match port.parse::<u16>() {
+                Ok(port) if port > 0 => Some(port),
+                _ => return Err(format!("'{}' has an invalid port", entry)),
+            },
+            None => None,
+        };
+
+        Ok(HostAddress {
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    /// The host as written in URIs, bracketing IPv6 addresses
+    pub fn uri_host(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        }
+    }
+}
+
+impl fmt::Display for HostAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => write!(f, "{}:{}", self.uri_host(), port),
+            None => write!(f, "{}", self.uri_host()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_address() {
+        let parse = |entry| HostAddress::parse(entry).map(|a| (a.host, a.port));
+        assert_eq!(parse("pg1"), Ok(("pg1".to_string(), None)));
+        assert_eq!(parse("pg1:5433"), Ok(("pg1".to_string(), Some(5433))));
+        assert_eq!(parse("[::1]:5433"), Ok(("::1".to_string(), Some(5433))));
+        assert_eq!(parse("fe80::1"), Ok(("fe80::1".to_string(), None)));
+        assert!(parse("pg1:0").is_err());
+        assert!(parse("pg1:http").is_err());
+        assert!(parse(":5432").is_err());
+        assert!(parse("[::1").is_err());
+
+        let address = HostAddress::parse("[::1]:27017").unwrap();
+        assert_eq!(address.to_string(), "[::1]:27017");
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        #[derive(Deserialize)]
+        struct Alias {
+            host_strategy: HostStrategy,
+        }
+        let alias: Alias = toml::from_str("host_strategy = \"engine-native\"").unwrap();
+        assert_eq!(alias.host_strategy, HostStrategy::EngineNative);
+        assert!(toml::from_str::<Alias>("host_strategy = \"round-robin\"").is_err());
+    }
+}
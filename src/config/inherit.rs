@@ -0,0 +1,297 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::config::diagnostic::locate;
+use crate::config::{Config, DatabaseConfig, Diagnostic, EnvironmentConfig, TemplateConfig};
+use crate::error::{DbJumpError, Result};
+
+/// Keys that identify an entry and are never inherited by its children
+const NON_INHERITED_KEYS: &[&str] = &["alias", "name", "extends"];
+
+/// A `[[database]]` table as written, before `extends` is applied
+pub(crate) struct RawDatabase {
+    pub table: toml::Table,
+    pub source: PathBuf,
+}
+
+/// Everything merged from the config files, before inheritance is resolved
+#[derive(Default)]
+pub(crate) struct RawConfig {
+    pub environments: BTreeMap<String, EnvironmentConfig>,
+    pub templates: Vec<TemplateConfig>,
+    pub databases: Vec<RawDatabase>,
+    /// Files that could not be parsed
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl RawConfig {
+    /// Merge `other` on top of this config. Templates and aliases defined in
+    /// both are replaced in place; new ones are appended.
+    pub fn overlay(&mut self, other: RawConfig) {
+        self.environments.extend(other.environments);
+        self.diagnostics.extend(other.diagnostics);
+
+        for template in other.templates {
+            match self.templates.iter_mut().find(|t| t.name == template.name) {
+                Some(existing) => *existing = template,
+                None => self.templates.push(template),
+            }
+        }
+
+        for db in other.databases {
+            let alias = table_str(&db.table, "alias");
+            match self
+                .databases
+                .iter_mut()
+                .find(|existing| alias.is_some() && table_str(&existing.table, "alias") == alias)
+            {
+                Some(existing) => *existing = db,
+                None => self.databases.push(db),
+            }
+        }
+    }
+}
+
+fn table_str<'a>(table: &'a toml::Table, key: &str) -> Option<&'a str> {
+    table.get(key).and_then(|value| value.as_str())
+}
+
+/// Apply `extends` to every database entry and deserialize the result.
+/// Parents may be templates or other aliases; templates win on a name clash.
+/// Every entry that fails is reported, not just the first.
+pub(crate) fn resolve(
+    templates: &[TemplateConfig],
+    databases: Vec<RawDatabase>,
+    pending: Option<(&Path, &str)>,
+) -> Result<Vec<DatabaseConfig>> {
+    let mut parents: HashMap<&str, (&toml::Table, Option<&str>)> = HashMap::new();
+    for db in &databases {
+        if let Some(alias) = table_str(&db.table, "alias") {
+            parents
+                .entry(alias)
+                .or_insert((&db.table, table_str(&db.table, "extends")));
+        }
+    }
+    for template in templates {
+        parents.insert(
+            &template.name,
+            (&template.fields, template.extends.as_deref()),
+        );
+    }
+
+    let mut resolved = Vec::new();
+    let mut diagnostics = Vec::new();
+    for db in &databases {
+        match resolve_database(db, &parents) {
+            Ok(config) => resolved.push(config),
+            Err(diagnostic) => diagnostics.push(*diagnostic),
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(resolved)
+    } else {
+        locate(&mut diagnostics, pending);
+        Err(DbJumpError::InvalidConfig(diagnostics))
+    }
+}
+
+fn resolve_database(
+    db: &RawDatabase,
+    parents: &HashMap<&str, (&toml::Table, Option<&str>)>,
+) -> std::result::Result<DatabaseConfig, Box<Diagnostic>> {
+    let alias = table_str(&db.table, "alias")
+        .unwrap_or_default()
+        .to_string();
+    let diagnostic = |message: String| {
+        Diagnostic::error(message)
+            .alias(&alias)
+            .file(Some(&db.source))
+    };
+    let mut table = db.table.clone();
+    let mut inherited = BTreeMap::new();
+    let mut chain = vec![alias.clone()];
+    let mut next = table_str(&db.table, "extends");
+
+    // Walk up the chain; the nearest parent that sets a field wins
+    while let Some(parent_name) = next {
+        if chain.iter().any(|name| name == parent_name) {
+            chain.push(parent_name.to_string());
+            let message = DbJumpError::InheritanceCycle(chain.join(" -> ")).to_string();
+            return Err(Box::new(diagnostic(message).field("extends")));
+        }
+
+        let Some((fields, extends)) = parents.get(parent_name) else {
+            let message = DbJumpError::UnknownParent(
+                chain.last().cloned().unwrap_or_default(),
+                parent_name.to_string(),
+            )
+            .to_string();
+            return Err(Box::new(diagnostic(message).field("extends")));
+        };
+
+        for (key, value) in fields.iter() {
+            if NON_INHERITED_KEYS.contains(&key.as_str()) || table.contains_key(key) {
+                continue;
+            }
+            table.insert(key.clone(), value.clone());
+            inherited.insert(key.clone(), parent_name.to_string());
+        }
+
+        chain.push(parent_name.to_string());
+        next = *extends;
+    }
+
+    let mut config: DatabaseConfig = toml::Value::Table(table).try_into().map_err(|e| {
+        // Errors for a specific key end with "in `key`"
+        let message = e.to_string();
+        let message = message.trim_end();
+        Box::new(match message.rsplit_once("\nin `") {
+            Some((message, field)) => diagnostic(format!("alias '{}': {}", alias, message))
+                .field(field.trim_end_matches('`')),
+            None => diagnostic(format!("alias '{}': {}", alias, message)),
+        })
+    })?;
+    config.inherited = inherited;
+    config.source = Some(db.source.clone());
+
+    Ok(config)
+}
+
+/// Check that every `extends` points at an existing template or alias and
+/// that no chain loops back on itself
+pub fn check_inheritance(config: &Config) -> Vec<Diagnostic> {
+    let mut parents: HashMap<&str, Option<&str>> = HashMap::new();
+    for db in &config.database {
+        parents.entry(&db.alias).or_insert(db.extends.as_deref());
+    }
+    for template in &config.template {
+        parents.insert(&template.name, template.extends.as_deref());
+    }
+
+    let entries = config
+        .database
+        .iter()
+        .map(|db| {
+            (
+                db.alias.as_str(),
+                db.extends.as_deref(),
+                db.source.as_deref(),
+            )
+        })
+        .chain(
+            config
+                .template
+                .iter()
+                .map(|t| (t.name.as_str(), t.extends.as_deref(), t.source.as_deref())),
+        );
+
+    let mut diagnostics = Vec::new();
+    for (name, extends, source) in entries {
+        let mut chain = vec![name];
+        let mut next = extends;
+        while let Some(parent_name) = next {
+            let error = if chain.contains(&parent_name) {
+                chain.push(parent_name);
+                Some(DbJumpError::InheritanceCycle(chain.join(" -> ")))
+            } else if let Some(parent_extends) = parents.get(parent_name) {
+                chain.push(parent_name);
+                next = *parent_extends;
+                None
+            } else {
+                Some(DbJumpError::UnknownParent(
+                    chain.last().unwrap_or(&name).to_string(),
+                    parent_name.to_string(),
+                ))
+            };
+
+            if let Some(error) = error {
+                diagnostics.push(
+                    Diagnostic::error(error.to_string())
+                        .alias(name)
+                        .field("extends")
+                        .file(source),
+                );
+                break;
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseEngine;
+
+    fn raw(toml_str: &str) -> RawDatabase {
+        RawDatabase {
+            table: toml::from_str(toml_str).unwrap(),
+            source: PathBuf::from("config.toml"),
+        }
+    }
+
+    fn template(toml_str: &str) -> TemplateConfig {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_template_chain() {
+        let templates = vec![
+            template("name = \"pg\"\nengine = \"postgresql\"\nuser = \"app\"\nport = 5432"),
+            template(
+                "name = \"pg-shard\"\nextends = \"pg\"\nhost = \"shard.internal\"\nport = 6432",
+            ),
+        ];
+        let databases = vec![raw(
+            "alias = \"shard-1\"\nextends = \"pg-shard\"\ndatabase = \"s1\"",
+        )];
+
+        let resolved = resolve(&templates, databases, None).unwrap();
+        let shard = &resolved[0];
+        assert_eq!(shard.engine, DatabaseEngine::PostgreSQL);
+        assert_eq!(shard.port, Some(6432));
+        assert_eq!(shard.database.as_deref(), Some("s1"));
+        assert_eq!(
+            shard.inherited.get("port").map(String::as_str),
+            Some("pg-shard")
+        );
+        assert_eq!(shard.inherited.get("user").map(String::as_str), Some("pg"));
+        assert!(!shard.inherited.contains_key("database"));
+    }
+
+    #[test]
+    fn test_resolve_reports_every_failure() {
+        let databases = vec![
+            raw("alias = \"a\"\nextends = \"nope\""),
+            raw("alias = \"b\"\nengine = \"mysql\"\nport = \"abc\""),
+        ];
+        match resolve(&[], databases, None) {
+            Err(DbJumpError::InvalidConfig(diagnostics)) => {
+                assert_eq!(diagnostics.len(), 2);
+                assert!(diagnostics[0]
+                    .message
+                    .contains("unknown template or alias 'nope'"));
+                assert_eq!(diagnostics[1].field.as_deref(), Some("port"));
+            }
+            other => panic!("expected diagnostics, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_check_inheritance_detects_cycle() {
+        let config = Config {
+            template: vec![
+                template("name = \"a\"\nextends = \"b\""),
+                template("name = \"b\"\nextends = \"a\""),
+            ],
+            ..Default::default()
+        };
+        let diagnostics = check_inheritance(&config);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0]
+            .message
+            .starts_with("Inheritance cycle detected: a -> b -> a"));
+    }
+}
@@ -1,7 +1,20 @@
+pub mod diagnostic;
+pub mod environment;
+pub mod hosts;
+pub mod inherit;
 pub mod parser;
 pub mod path;
+pub mod tls;
+pub mod trust;
 pub mod validator;
+pub mod writer;
 
-pub use parser::{Config, DatabaseConfig, DatabaseEngine};
-pub use path::get_config_path;
-pub use validator::validate_config;
+pub use diagnostic::{Diagnostic, Severity};
+pub use environment::{is_prod_environment, EnvironmentConfig, PromptColor};
+pub use hosts::{HostAddress, HostStrategy};
+pub use parser::{Config, DatabaseConfig, DatabaseEngine, TemplateConfig};
+pub use path::{find_project_config, get_config_path};
+pub use tls::{TlsConfig, TlsMode};
+pub use trust::TrustStore;
+pub use validator::{check_config, validate_config};
+pub use writer::ConfigFile;
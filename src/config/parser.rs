@@ -1,12 +1,51 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::config::inherit::{resolve, RawConfig, RawDatabase};
+use crate::config::{
+    is_prod_environment, Diagnostic, EnvironmentConfig, HostAddress, HostStrategy, PromptColor,
+    TlsConfig,
+};
 use crate::error::{DbJumpError, Result};
+use crate::utils::{edit_distance, expand_tilde};
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+const CONF_D_DIR_NAME: &str = "conf.d";
+/// How many aliases to suggest when a lookup fails
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Config {
+    /// Additional files to merge, relative to the including file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Per-environment settings, e.g. `[environments.prod]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environments: BTreeMap<String, EnvironmentConfig>,
+    /// Abstract entries that aliases can inherit from with `extends`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub template: Vec<TemplateConfig>,
+    #[serde(default)]
     pub database: Vec<DatabaseConfig>,
+    /// Edit read in place of a file by `load_with`, keyed by canonical path
+    #[serde(skip)]
+    pub pending: Option<(PathBuf, String)>,
+    /// Directory of the main config file, which holds conf.d
+    #[serde(skip)]
+    pub config_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TemplateConfig {
+    pub name: String,
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Any `[[database]]` fields, applied to aliases that don't set them
+    #[serde(flatten)]
+    pub fields: toml::Table,
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -15,6 +54,62 @@ pub struct DatabaseConfig {
     pub engine: DatabaseEngine,
     #[serde(default)]
     pub host: Option<String>,
+    /// Unix domain socket: its directory for PostgreSQL, the socket file
+    /// itself for MySQL and MongoDB. Can't be combined with `host`, nor with
+    /// `port` except for PostgreSQL, where the port picks the
+    /// `.s.PGSQL.<port>` file in that directory.
+    #[serde(default)]
+    pub socket: Option<String>,
+    /// Several `host[:port]` endpoints to fail over between, instead of `host`
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// How to pick among `hosts`; first-reachable when not set
+    #[serde(default)]
+    pub host_strategy: Option<HostStrategy>,
+    /// MongoDB replica set name
+    #[serde(default)]
+    pub replica_set: Option<String>,
+    /// Look MongoDB hosts up through DNS SRV records (`mongodb+srv://`)
+    #[serde(default)]
+    pub srv: bool,
+    /// MongoDB database that holds the user's credentials
+    #[serde(default)]
+    pub auth_source: Option<String>,
+    /// MongoDB authentication mechanism, e.g. SCRAM-SHA-256 or MONGODB-X509
+    #[serde(default)]
+    pub auth_mechanism: Option<String>,
+    /// Connect to ClickHouse over TLS on the secure native port, as with an
+    /// empty `tls` table
+    #[serde(default)]
+    pub secure: bool,
+    /// Session settings, e.g. `{ max_threads = 8 }` for ClickHouse or
+    /// `{ work_mem = "64MB" }` for PostgreSQL
+    #[serde(default)]
+    pub settings: BTreeMap<String, toml::Value>,
+    /// PostgreSQL service from pg_service.conf to take defaults from
+    #[serde(default)]
+    pub service: Option<String>,
+    /// PostgreSQL application_name, shown in pg_stat_activity
+    #[serde(default)]
+    pub application_name: Option<String>,
+    /// PostgreSQL schema search path, e.g. "app, public"
+    #[serde(default)]
+    pub search_path: Option<String>,
+    /// PostgreSQL role to switch to after connecting
+    #[serde(default)]
+    pub role: Option<String>,
+    /// PostgreSQL statement timeout, e.g. "30s"
+    #[serde(default)]
+    pub statement_timeout: Option<String>,
+    /// MySQL client character set, e.g. "utf8mb4"
+    #[serde(default)]
+    pub charset: Option<String>,
+    /// MySQL statement to run after connecting
+    #[serde(default)]
+    pub init_command: Option<String>,
+    /// Extra MongoDB connection string options, e.g. `{ retryWrites = false }`
+    #[serde(default)]
+    pub uri_options: BTreeMap<String, toml::Value>,
     #[serde(default)]
     pub port: Option<u16>,
     #[serde(default)]
@@ -25,10 +120,41 @@ pub struct DatabaseConfig {
     pub database: Option<String>,
     #[serde(default)]
     pub options: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// TLS settings, translated into the client's own flags
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Deployment environment, e.g. "prod" or "staging"
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// Ask before connecting; defaults to true for the "prod" or "production"
+    /// environment
+    #[serde(default)]
+    pub confirm: Option<bool>,
+    /// Open the session in read-only mode where the engine supports it
+    #[serde(default)]
+    pub read_only: bool,
+    /// Record the session transcript under ~/.local/state/dbjump/sessions
+    #[serde(default)]
+    pub record: bool,
+    /// Template or alias this entry inherits unset fields from
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Prompt color for this alias's environment, resolved at load time
+    #[serde(skip)]
+    pub prompt_color: Option<PromptColor>,
+    /// Inherited fields, mapped to the template or alias that supplied them
+    #[serde(skip)]
+    pub inherited: BTreeMap<String, String>,
+    /// File this alias was loaded from
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, clap::ValueEnum)]
 #[serde(rename_all = "lowercase")]
+#[value(rename_all = "lowercase")]
 pub enum DatabaseEngine {
     ClickHouse,
     PostgreSQL,
@@ -37,22 +163,127 @@ pub enum DatabaseEngine {
 }
 
 impl Config {
+    /// Load the configuration file together with its `include` files and
+    /// the `conf.d/*.toml` fragments next to it.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = fs::read_to_string(path.as_ref()).map_err(|e| match e.kind() {
-            std::io::ErrorKind::NotFound => {
-                DbJumpError::ConfigNotFound(path.as_ref().display().to_string())
+        Self::load(path.as_ref(), None)
+    }
+
+    /// Load the global configuration, merge a project-local file on top of it,
+    /// then resolve `extends` inheritance across everything that was merged.
+    /// The project file's includes are honored, but no conf.d next to it.
+    pub fn load(path: &Path, project_path: Option<&Path>) -> Result<Self> {
+        Self::load_with(path, project_path, None)
+    }
+
+    /// Like `load`, but read `pending.1` in place of the file at `pending.0`.
+    /// Used to check an edit against the whole configuration before it is
+    /// written to disk.
+    pub fn load_with(
+        path: &Path,
+        project_path: Option<&Path>,
+        pending: Option<(&Path, &str)>,
+    ) -> Result<Self> {
+        let pending_key =
+            pending.map(|(pending_path, content)| (canonical_key(pending_path), content));
+        let pending = pending_key
+            .as_ref()
+            .map(|(key, content)| (key.as_path(), *content));
+        let mut raw = RawConfig::default();
+        let mut visited = HashSet::new();
+
+        load_layer(&mut raw, path, &mut visited, pending)?;
+
+        if let Some(dir) = path.parent() {
+            for fragment in conf_d_files(&conf_d_dir(dir))? {
+                load_layer(&mut raw, &fragment, &mut visited, pending)?;
+            }
+        }
+
+        if let Some(project_path) = project_path {
+            let mut project = RawConfig::default();
+            load_layer(&mut project, project_path, &mut HashSet::new(), pending)?;
+            raw.overlay(project);
+        }
+
+        if !raw.diagnostics.is_empty() {
+            return Err(DbJumpError::InvalidConfig(raw.diagnostics));
+        }
+
+        let mut database = resolve(&raw.templates, raw.databases, pending)?;
+        for db in &mut database {
+            if let Some(tls) = &mut db.tls {
+                tls.resolve_paths(db.source.as_deref().and_then(Path::parent));
             }
-            _ => DbJumpError::IoError(e),
-        })?;
+            db.prompt_color = db.environment.as_deref().and_then(|env| {
+                raw.environments
+                    .get(env)
+                    .and_then(|settings| settings.color)
+                    .or_else(|| PromptColor::default_for(env))
+            });
+        }
+
+        Ok(Config {
+            include: Vec::new(),
+            environments: raw.environments,
+            template: raw.templates,
+            database,
+            pending: pending.map(|(path, content)| (path.to_path_buf(), content.to_string())),
+            config_dir: path.parent().map(Path::to_path_buf),
+        })
+    }
 
-        toml::from_str(&content).map_err(|e| DbJumpError::ConfigParseError(e.to_string()))
+    /// The pending edit this configuration was loaded with, if any
+    pub fn pending(&self) -> Option<(&Path, &str)> {
+        self.pending
+            .as_ref()
+            .map(|(path, content)| (path.as_path(), content.as_str()))
     }
 
+    /// Look up an alias, falling back to a unique prefix or substring match,
+    /// in that order. On failure the error carries the closest aliases.
     pub fn find_by_alias(&self, alias: &str) -> Result<&DatabaseConfig> {
-        self.database
-            .iter()
-            .find(|db| db.alias == alias)
-            .ok_or_else(|| DbJumpError::AliasNotFound(alias.to_string()))
+        if let Some(db) = self.database.iter().find(|db| db.alias == alias) {
+            return Ok(db);
+        }
+
+        let matchers: [fn(&str, &str) -> bool; 2] = [
+            |alias, query| alias.starts_with(query),
+            |alias, query| alias.contains(query),
+        ];
+        let mut candidates = Vec::new();
+        for matches in matchers {
+            candidates = self
+                .database
+                .iter()
+                .filter(|db| matches(&db.alias, alias))
+                .collect();
+            if !candidates.is_empty() {
+                break;
+            }
+        }
+        if let [db] = candidates[..] {
+            return Ok(db);
+        }
+
+        if candidates.is_empty() {
+            let max_distance = (alias.chars().count() / 3).max(2);
+            candidates = self
+                .database
+                .iter()
+                .filter(|db| edit_distance(alias, &db.alias) <= max_distance)
+                .collect();
+        }
+        candidates.sort_by_key(|db| (edit_distance(alias, &db.alias), &db.alias));
+
+        Err(DbJumpError::AliasNotFound(
+            alias.to_string(),
+            candidates
+                .iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|db| db.alias.clone())
+                .collect(),
+        ))
     }
 
     pub fn get_all_aliases(&self) -> Vec<String> {
@@ -60,21 +291,266 @@ impl Config {
     }
 }
 
+/// Contents of a single file before inheritance is resolved
+#[derive(Deserialize)]
+struct Layer {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    environments: BTreeMap<String, EnvironmentConfig>,
+    #[serde(default)]
+    template: Vec<TemplateConfig>,
+    #[serde(default)]
+    database: Vec<toml::Table>,
+}
+
+fn parse_file(path: &Path) -> Result<Layer> {
+    let content = fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => DbJumpError::ConfigNotFound(path.display().to_string()),
+        _ => DbJumpError::IoError(e),
+    })?;
+
+    parse_content(path, &content)
+}
+
+fn parse_content(path: &Path, content: &str) -> Result<Layer> {
+    toml::from_str(content).map_err(|e| {
+        let diagnostic = Diagnostic::error(e.message()).file(Some(path));
+        let diagnostic = match e.span() {
+            Some(span) => diagnostic.span(content, span),
+            None => diagnostic,
+        };
+        DbJumpError::InvalidConfig(vec![diagnostic])
+    })
+}
+
+fn canonical_key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn load_layer(
+    raw: &mut RawConfig,
+    path: &Path,
+    visited: &mut HashSet<PathBuf>,
+    pending: Option<(&Path, &str)>,
+) -> Result<()> {
+    // Each file is merged at most once, which also breaks include cycles
+    let key = canonical_key(path);
+    if !visited.insert(key.clone()) {
+        return Ok(());
+    }
+
+    // `pending` is keyed by canonical path
+    let layer = match pending {
+        Some((pending_key, content)) if pending_key == key => parse_content(path, content),
+        _ => parse_file(path),
+    };
+    // Keep going so that every broken file is reported at once
+    let layer = match layer {
+        Ok(layer) => layer,
+        Err(DbJumpError::InvalidConfig(diagnostics)) => {
+            raw.diagnostics.extend(diagnostics);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    raw.environments.extend(layer.environments);
+    raw.templates
+        .extend(layer.template.into_iter().map(|mut template| {
+            template.source = Some(path.to_path_buf());
+            template
+        }));
+    raw.databases
+        .extend(layer.database.into_iter().map(|table| RawDatabase {
+            table,
+            source: path.to_path_buf(),
+        }));
+
+    for include_path in include_paths(path, &layer.include) {
+        load_layer(raw, &include_path, visited, pending)?;
+    }
+
+    Ok(())
+}
+
+/// Resolve the `include` entries of the file at `path`, relative to its
+/// directory
+pub(crate) fn include_paths(path: &Path, includes: &[String]) -> Vec<PathBuf> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    includes
+        .iter()
+        .map(|include| {
+            let include_path = expand_tilde(include);
+            if include_path.is_absolute() {
+                include_path
+            } else {
+                base_dir.join(include_path)
+            }
+        })
+        .collect()
+}
+
+/// The conf.d directory next to a main config file in `config_dir`
+pub(crate) fn conf_d_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join(CONF_D_DIR_NAME)
+}
+
+/// List non-hidden `*.toml` files in a conf.d directory, sorted by file name
+fn conf_d_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        // Hidden files are editor scratch copies, e.g. from `dbjump edit`
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_file() && !hidden && path.extension().is_some_and(|ext| ext == "toml") {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
 impl DatabaseConfig {
+    pub fn new(alias: &str, engine: DatabaseEngine) -> Self {
+        DatabaseConfig {
+            alias: alias.to_string(),
+            engine,
+            host: None,
+            socket: None,
+            hosts: Vec::new(),
+            host_strategy: None,
+            replica_set: None,
+            srv: false,
+            secure: false,
+            settings: BTreeMap::new(),
+            service: None,
+            application_name: None,
+            search_path: None,
+            role: None,
+            statement_timeout: None,
+            charset: None,
+            init_command: None,
+            auth_source: None,
+            auth_mechanism: None,
+            uri_options: BTreeMap::new(),
+            port: None,
+            user: None,
+            password: None,
+            database: None,
+            options: Vec::new(),
+            tags: Vec::new(),
+            tls: None,
+            environment: None,
+            confirm: None,
+            read_only: false,
+            record: false,
+            extends: None,
+            prompt_color: None,
+            inherited: BTreeMap::new(),
+            source: None,
+        }
+    }
+
+    pub fn host_strategy(&self) -> HostStrategy {
+        self.host_strategy.unwrap_or_default()
+    }
+
+    /// The parsed `hosts` entries, skipping any that don't parse
+    pub fn host_addresses(&self) -> Vec<HostAddress> {
+        self.hosts
+            .iter()
+            .filter_map(|entry| HostAddress::parse(entry).ok())
+            .collect()
+    }
+
+    /// Every host the alias may connect to: `host`, or each entry of `hosts`
+    pub fn host_names(&self) -> Vec<String> {
+        self.host
+            .iter()
+            .cloned()
+            .chain(
+                self.host_addresses()
+                    .into_iter()
+                    .map(|address| address.host),
+            )
+            .collect()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn requires_confirmation(&self) -> bool {
+        self.confirm
+            .unwrap_or_else(|| self.environment.as_deref().is_some_and(is_prod_environment))
+    }
+
+    /// Describe where this alias was defined, for error messages
+    pub fn source_display(&self) -> String {
+        self.source
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "configuration".to_string())
+    }
+
     pub fn format_info(&self, hide_password: bool) -> String {
         let mut lines = vec![format!("  Alias: {}", self.alias)];
-        lines.push(format!("  Engine: {:?}", self.engine));
+        lines.push(format!(
+            "  Engine: {:?}{}",
+            self.engine,
+            self.origin("engine")
+        ));
+
+        if let Some(ref extends) = self.extends {
+            lines.push(format!("  Extends: {}", extends));
+        }
+
+        if let Some(ref environment) = self.environment {
+            lines.push(format!(
+                "  Environment: {}{}",
+                environment,
+                self.origin("environment")
+            ));
+        }
+
+        if !self.tags.is_empty() {
+            lines.push(format!(
+                "  Tags: {}{}",
+                self.tags.join(", "),
+                self.origin("tags")
+            ));
+        }
 
         if let Some(ref host) = self.host {
-            lines.push(format!("  Host: {}", host));
+            lines.push(format!("  Host: {}{}", host, self.origin("host")));
+        }
+
+        if !self.hosts.is_empty() {
+            lines.push(format!(
+                "  Hosts: {} ({}){}",
+                self.hosts.join(", "),
+                self.host_strategy(),
+                self.origin("hosts")
+            ));
+        }
+
+        if let Some(ref socket) = self.socket {
+            lines.push(format!("  Socket: {}{}", socket, self.origin("socket")));
         }
 
         if let Some(port) = self.port {
-            lines.push(format!("  Port: {}", port));
+            lines.push(format!("  Port: {}{}", port, self.origin("port")));
         }
 
         if let Some(ref user) = self.user {
-            lines.push(format!("  User: {}", user));
+            lines.push(format!("  User: {}{}", user, self.origin("user")));
         }
 
         if let Some(ref password) = self.password {
@@ -83,19 +559,134 @@ impl DatabaseConfig {
             } else {
                 password.clone()
             };
-            lines.push(format!("  Password: {}", password_display));
+            lines.push(format!(
+                "  Password: {}{}",
+                password_display,
+                self.origin("password")
+            ));
         }
 
         if let Some(ref database) = self.database {
-            lines.push(format!("  Database: {}", database));
+            lines.push(format!(
+                "  Database: {}{}",
+                database,
+                self.origin("database")
+            ));
+        }
+
+        if let Some(ref replica_set) = self.replica_set {
+            lines.push(format!(
+                "  Replica set: {}{}",
+                replica_set,
+                self.origin("replica_set")
+            ));
+        }
+
+        if self.srv {
+            lines.push(format!("  SRV lookup: yes{}", self.origin("srv")));
+        }
+
+        let auth = [
+            ("Auth source", "auth_source", &self.auth_source),
+            ("Auth mechanism", "auth_mechanism", &self.auth_mechanism),
+        ];
+        for (label, field, value) in auth {
+            if let Some(value) = value {
+                lines.push(format!("  {}: {}{}", label, value, self.origin(field)));
+            }
+        }
+
+        let postgres = [
+            ("Service", "service", &self.service),
+            (
+                "Application name",
+                "application_name",
+                &self.application_name,
+            ),
+            ("Search path", "search_path", &self.search_path),
+            ("Role", "role", &self.role),
+            (
+                "Statement timeout",
+                "statement_timeout",
+                &self.statement_timeout,
+            ),
+        ];
+        for (label, field, value) in postgres {
+            if let Some(value) = value {
+                lines.push(format!("  {}: {}{}", label, value, self.origin(field)));
+            }
+        }
+
+        let mysql = [
+            ("Charset", "charset", &self.charset),
+            ("Init command", "init_command", &self.init_command),
+        ];
+        for (label, field, value) in mysql {
+            if let Some(value) = value {
+                lines.push(format!("  {}: {}{}", label, value, self.origin(field)));
+            }
+        }
+
+        if self.secure {
+            lines.push(format!("  Secure: yes{}", self.origin("secure")));
+        }
+
+        if !self.settings.is_empty() {
+            let settings: Vec<_> = self
+                .settings
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            lines.push(format!(
+                "  Settings: {}{}",
+                settings.join(" "),
+                self.origin("settings")
+            ));
+        }
+
+        if !self.uri_options.is_empty() {
+            let options: Vec<_> = self
+                .uri_options
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            lines.push(format!(
+                "  URI options: {}{}",
+                options.join(" "),
+                self.origin("uri_options")
+            ));
+        }
+
+        if let Some(ref tls) = self.tls {
+            lines.push(format!("  TLS: {}{}", tls.summary(), self.origin("tls")));
+        }
+
+        if self.read_only {
+            lines.push(format!("  Read-only: yes{}", self.origin("read_only")));
+        }
+
+        if self.record {
+            lines.push(format!("  Record: yes{}", self.origin("record")));
         }
 
         if !self.options.is_empty() {
-            lines.push(format!("  Options: {}", self.options.join(" ")));
+            lines.push(format!(
+                "  Options: {}{}",
+                self.options.join(" "),
+                self.origin("options")
+            ));
         }
 
         lines.join("\n")
     }
+
+    /// Annotation naming the template a field was inherited from
+    fn origin(&self, field: &str) -> String {
+        self.inherited
+            .get(field)
+            .map(|parent| format!("  (from {})", parent))
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -122,6 +713,73 @@ options = ["--multiline"]
         assert_eq!(config.database[0].engine, DatabaseEngine::ClickHouse);
     }
 
+    #[test]
+    fn test_find_by_alias_fuzzy() {
+        let config = Config {
+            database: ["prod-clickhouse", "prod-postgres", "staging-postgres"]
+                .iter()
+                .map(|alias| DatabaseConfig::new(alias, DatabaseEngine::PostgreSQL))
+                .collect(),
+            ..Default::default()
+        };
+
+        let alias = |query| config.find_by_alias(query).map(|db| db.alias.as_str());
+        assert_eq!(alias("prod-cl").unwrap(), "prod-clickhouse");
+        assert_eq!(alias("staging").unwrap(), "staging-postgres");
+        assert_eq!(alias("click").unwrap(), "prod-clickhouse");
+        // Scattered letters never pick an alias on their own
+        assert!(alias("stgpg").is_err());
+
+        match alias("prod") {
+            Err(DbJumpError::AliasNotFound(_, suggestions)) => {
+                assert_eq!(suggestions, vec!["prod-postgres", "prod-clickhouse"])
+            }
+            other => panic!("expected ambiguous match, got {:?}", other),
+        }
+        match alias("prod-postgers") {
+            Err(DbJumpError::AliasNotFound(_, suggestions)) => {
+                assert_eq!(suggestions, vec!["prod-postgres"])
+            }
+            other => panic!("expected suggestions, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_tags_and_environment() {
+        let toml_str = r#"
+[[database]]
+alias = "prod-analytics"
+engine = "clickhouse"
+tags = ["prod", "analytics"]
+environment = "prod"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let db = &config.database[0];
+        assert!(db.has_tag("analytics"));
+        assert!(!db.has_tag("staging"));
+        assert_eq!(db.environment.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn test_requires_confirmation() {
+        let mut db = DatabaseConfig::new("db", DatabaseEngine::PostgreSQL);
+        assert!(!db.requires_confirmation());
+
+        db.environment = Some("prod".to_string());
+        assert!(db.requires_confirmation());
+
+        db.environment = Some("production".to_string());
+        assert!(db.requires_confirmation());
+
+        db.confirm = Some(false);
+        assert!(!db.requires_confirmation());
+
+        db.environment = Some("staging".to_string());
+        db.confirm = Some(true);
+        assert!(db.requires_confirmation());
+    }
+
     #[test]
     fn test_parse_config_minimal() {
         let toml_str = r#"
@@ -138,4 +796,181 @@ engine = "clickhouse"
         assert!(config.database[0].user.is_none());
         assert!(config.database[0].password.is_none());
     }
+
+    #[test]
+    fn test_from_file_merges_includes_and_conf_d() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+include = ["team.toml"]
+
+[[database]]
+alias = "main-db"
+engine = "postgresql"
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("team.toml"),
+            r#"
+[[database]]
+alias = "team-db"
+engine = "mysql"
+            "#,
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("conf.d")).unwrap();
+        fs::write(
+            dir.path().join("conf.d").join("private.toml"),
+            r#"
+[[database]]
+alias = "private-db"
+engine = "mongodb"
+            "#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("conf.d").join("notes.txt"), "ignored").unwrap();
+        fs::write(
+            dir.path().join("conf.d").join(".private.edit.toml"),
+            "not = [valid",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        assert_eq!(
+            config.get_all_aliases(),
+            vec!["main-db", "team-db", "private-db"]
+        );
+        assert_eq!(
+            config.find_by_alias("team-db").unwrap().source,
+            Some(dir.path().join("team.toml"))
+        );
+    }
+
+    #[test]
+    fn test_from_file_resolves_prompt_colors() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+[environments.qa]
+color = "cyan"
+
+[[database]]
+alias = "qa-db"
+engine = "mysql"
+environment = "qa"
+
+[[database]]
+alias = "prod-db"
+engine = "mysql"
+environment = "prod"
+
+[[database]]
+alias = "local-db"
+engine = "mysql"
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        let color = |alias| config.find_by_alias(alias).unwrap().prompt_color;
+        assert_eq!(color("qa-db"), Some(PromptColor::Cyan));
+        assert_eq!(color("prod-db"), Some(PromptColor::Red));
+        assert_eq!(color("local-db"), None);
+    }
+
+    #[test]
+    fn test_load_with_pending_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(&config_path, "include = [\"team.toml\"]\n").unwrap();
+        let team_path = dir.path().join("team.toml");
+        fs::write(&team_path, "").unwrap();
+
+        let pending = "[[database]]\nalias = \"new-db\"\nengine = \"mysql\"\n";
+        let config = Config::load_with(&config_path, None, Some((&team_path, pending))).unwrap();
+        assert_eq!(config.get_all_aliases(), vec!["new-db"]);
+        assert_eq!(fs::read_to_string(&team_path).unwrap(), "");
+    }
+
+    #[test]
+    fn test_from_file_include_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            "include = [\"other.toml\"]\n[[database]]\nalias = \"a\"\nengine = \"mysql\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("other.toml"),
+            "include = [\"config.toml\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        assert_eq!(config.get_all_aliases(), vec!["a"]);
+    }
+
+    #[test]
+    fn test_load_project_file_replaces_matching_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+[[template]]
+name = "pg-base"
+engine = "postgresql"
+host = "db.internal"
+
+[[database]]
+alias = "dev"
+extends = "pg-base"
+
+[[database]]
+alias = "prod"
+extends = "pg-base"
+            "#,
+        )
+        .unwrap();
+        let project_path = dir.path().join(".dbjump.toml");
+        fs::write(
+            &project_path,
+            r#"
+[[database]]
+alias = "dev"
+extends = "pg-base"
+host = "localhost"
+
+[[database]]
+alias = "staging"
+engine = "mysql"
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::load(&config_path, Some(&project_path)).unwrap();
+        assert_eq!(config.get_all_aliases(), vec!["dev", "prod", "staging"]);
+        let dev = config.find_by_alias("dev").unwrap();
+        assert_eq!(dev.engine, DatabaseEngine::PostgreSQL);
+        assert_eq!(dev.host.as_deref(), Some("localhost"));
+        assert_eq!(dev.source, Some(project_path));
+    }
+
+    #[test]
+    fn test_from_file_missing_include() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(&config_path, "include = [\"missing.toml\"]\n").unwrap();
+
+        assert!(matches!(
+            Config::from_file(&config_path),
+            Err(DbJumpError::ConfigNotFound(_))
+        ));
+    }
 }
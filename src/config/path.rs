@@ -1,12 +1,13 @@
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::error::{DbJumpError, Result};
 use crate::utils::{set_permissions_600, set_permissions_700};
 
 const DEFAULT_CONFIG_NAME: &str = "config.toml";
 const CONFIG_DIR_NAME: &str = "dbjump";
+pub const PROJECT_CONFIG_NAME: &str = ".dbjump.toml";
 
 pub fn get_config_path() -> Result<PathBuf> {
     // Check environment variable first
@@ -23,6 +24,35 @@ pub fn get_config_path() -> Result<PathBuf> {
     Ok(config_dir.join(DEFAULT_CONFIG_NAME))
 }
 
+/// Find the nearest `.dbjump.toml` walking up from the current directory
+pub fn find_project_config() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    find_project_config_from(&cwd)
+}
+
+pub fn find_project_config_from(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Directory for dbjump's own data (~/.local/share/dbjump)
+pub fn get_data_dir() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| DbJumpError::ConfigError("Cannot determine home directory".to_string()))?;
+
+    Ok(home_dir.join(".local").join("share").join(CONFIG_DIR_NAME))
+}
+
+/// Directory for dbjump's state such as logs (~/.local/state/dbjump)
+pub fn get_state_dir() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| DbJumpError::ConfigError("Cannot determine home directory".to_string()))?;
+
+    Ok(home_dir.join(".local").join("state").join(CONFIG_DIR_NAME))
+}
+
 pub fn ensure_config_dir() -> Result<PathBuf> {
     let config_path = get_config_path()?;
     let config_dir = config_path
@@ -95,3 +125,20 @@ pub fn init_config_file(force: bool) -> Result<PathBuf> {
 
     Ok(config_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_project_config_walks_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(find_project_config_from(&nested).is_none());
+
+        let project_file = dir.path().join("a").join(PROJECT_CONFIG_NAME);
+        fs::write(&project_file, "").unwrap();
+        assert_eq!(find_project_config_from(&nested), Some(project_file));
+    }
+}
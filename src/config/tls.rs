@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::utils::expand_tilde;
+
+/// Settings for an alias's `tls = { ... }` table, translated into each
+/// client's own flags, environment variables or URI parameters
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// How strictly to use TLS; `verify-full` when not set
+    #[serde(default)]
+    pub mode: Option<TlsMode>,
+    /// CA certificate(s) to verify the server with
+    #[serde(default)]
+    pub ca_file: Option<PathBuf>,
+    /// Client certificate, for servers that authenticate clients by certificate
+    #[serde(default)]
+    pub cert_file: Option<PathBuf>,
+    /// Private key for `cert_file`
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+    /// Name to verify the server certificate against, when it differs from
+    /// the host connected to
+    #[serde(default)]
+    pub server_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TlsMode {
+    /// Plain text connection
+    Disable,
+    /// Encrypt, but don't verify the server certificate
+    Require,
+    /// Verify the certificate chain, but not the host name
+    VerifyCa,
+    /// Verify the certificate chain and the host name
+    VerifyFull,
+}
+
+impl fmt::Display for TlsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsMode::Disable => write!(f, "disable"),
+            TlsMode::Require => write!(f, "require"),
+            TlsMode::VerifyCa => write!(f, "verify-ca"),
+            TlsMode::VerifyFull => write!(f, "verify-full"),
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn mode(&self) -> TlsMode {
+        self.mode.unwrap_or(TlsMode::VerifyFull)
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.mode() == TlsMode::Disable
+    }
+
+    /// The file fields that are set, as (field, path)
+    pub fn files(&self) -> Vec<(&'static str, &Path)> {
+        [
+            ("ca_file", &self.ca_file),
+            ("cert_file", &self.cert_file),
+            ("key_file", &self.key_file),
+        ]
+        .into_iter()
+        .filter_map(|(field, path)| path.as_deref().map(|path| (field, path)))
+        .collect()
+    }
+
+    /// Expand `~` and make relative paths relative to `base_dir`, the
+    /// directory of the file that defined the alias
+    pub fn resolve_paths(&mut self, base_dir: Option<&Path>) {
+        for path in [&mut self.ca_file, &mut self.cert_file, &mut self.key_file]
+            .into_iter()
+            .flatten()
+        {
+            let expanded = expand_tilde(&path.to_string_lossy());
+            *path = match base_dir {
+                Some(dir) if expanded.is_relative() => dir.join(expanded),
+                _ => expanded,
+            };
+        }
+    }
+
+    /// Short description for `dbjump info`
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.mode().to_string()];
+        for (field, path) in self.files() {
+            let name = field.trim_end_matches("_file");
+            parts.push(format!("{}: {}", name, path.display()));
+        }
+        if let Some(ref server_name) = self.server_name {
+            parts.push(format!("server name: {}", server_name));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_resolve_paths() {
+        let mut tls: TlsConfig = toml::from_str(
+            "mode = \"verify-ca\"\nca_file = \"certs/ca.pem\"\nkey_file = \"/k.pem\"",
+        )
+        .unwrap();
+        assert_eq!(tls.mode(), TlsMode::VerifyCa);
+
+        tls.resolve_paths(Some(Path::new("/etc/dbjump")));
+        assert_eq!(tls.ca_file, Some(PathBuf::from("/etc/dbjump/certs/ca.pem")));
+        assert_eq!(tls.key_file, Some(PathBuf::from("/k.pem")));
+        assert_eq!(
+            tls.summary(),
+            "verify-ca, ca: /etc/dbjump/certs/ca.pem, key: /k.pem"
+        );
+
+        assert_eq!(TlsConfig::default().mode(), TlsMode::VerifyFull);
+        assert!(toml::from_str::<TlsConfig>("mode = \"prefer\"").is_err());
+        assert!(toml::from_str::<TlsConfig>("ca = \"x\"").is_err());
+    }
+}
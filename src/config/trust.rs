@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::parser::include_paths;
+use crate::config::path::get_data_dir;
+use crate::error::{DbJumpError, Result};
+use crate::utils::{set_permissions_600, set_permissions_700};
+
+const TRUST_FILE_NAME: &str = "trusted.toml";
+
+/// Allow-list of project config files, keyed by path and pinned to a hash of
+/// the file and everything it includes. Editing any of them revokes trust
+/// until it is approved again.
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct TrustStore {
+    #[serde(default)]
+    trusted: BTreeMap<String, String>,
+}
+
+impl TrustStore {
+    pub fn load() -> Result<Self> {
+        Self::load_from(&get_trust_file_path()?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                toml::from_str(&content).map_err(|e| DbJumpError::ConfigParseError(e.to_string()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(DbJumpError::IoError(e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&get_trust_file_path()?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.exists() {
+                fs::create_dir_all(dir)?;
+                set_permissions_700(dir)?;
+            }
+        }
+
+        let content = toml::to_string(self).map_err(|e| DbJumpError::ConfigError(e.to_string()))?;
+        fs::write(path, content)?;
+        set_permissions_600(path)?;
+
+        Ok(())
+    }
+
+    pub fn is_trusted(&self, project_file: &Path) -> Result<bool> {
+        let key = trust_key(project_file)?;
+        Ok(self.trusted.get(&key) == Some(&hash_project(project_file)?))
+    }
+
+    pub fn trust(&mut self, project_file: &Path) -> Result<()> {
+        let key = trust_key(project_file)?;
+        self.trusted.insert(key, hash_project(project_file)?);
+        Ok(())
+    }
+
+    /// Returns false if the file was not trusted
+    pub fn revoke(&mut self, project_file: &Path) -> Result<bool> {
+        let key = trust_key(project_file)?;
+        Ok(self.trusted.remove(&key).is_some())
+    }
+}
+
+fn get_trust_file_path() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join(TRUST_FILE_NAME))
+}
+
+fn trust_key(project_file: &Path) -> Result<String> {
+    Ok(fs::canonicalize(project_file)?.display().to_string())
+}
+
+/// Just the part of a config file that pulls in other files
+#[derive(Deserialize, Default)]
+struct Includes {
+    #[serde(default)]
+    include: Vec<String>,
+}
+
+/// Hash the project file together with the files it includes, transitively
+fn hash_project(project_file: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(fs::read(project_file)?);
+    let mut visited = HashSet::from([fs::canonicalize(project_file)?]);
+    hash_includes(&mut hasher, project_file, &mut visited)?;
+
+    let digest = hasher.finalize();
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn hash_includes(hasher: &mut Sha256, path: &Path, visited: &mut HashSet<PathBuf>) -> Result<()> {
+    // A file that doesn't parse is rejected when the config is loaded
+    let content = fs::read_to_string(path)?;
+    let includes: Includes = toml::from_str(&content).unwrap_or_default();
+
+    for include_path in include_paths(path, &includes.include) {
+        let key = fs::canonicalize(&include_path).unwrap_or_else(|_| include_path.clone());
+        if !visited.insert(key) {
+            continue;
+        }
+
+        // Pin the path too, so that pointing an include elsewhere or
+        // creating a file that was missing revokes trust
+        hasher.update(include_path.display().to_string());
+        match fs::read(&include_path) {
+            Ok(bytes) => {
+                hasher.update([1]);
+                hasher.update((bytes.len() as u64).to_le_bytes());
+                hasher.update(bytes);
+                hash_includes(hasher, &include_path, visited)?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => hasher.update([0]),
+            Err(e) => return Err(DbJumpError::IoError(e)),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trust_is_pinned_to_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join(".dbjump.toml");
+        fs::write(&project_file, "# v1").unwrap();
+
+        let mut store = TrustStore::default();
+        assert!(!store.is_trusted(&project_file).unwrap());
+
+        store.trust(&project_file).unwrap();
+        assert!(store.is_trusted(&project_file).unwrap());
+
+        fs::write(&project_file, "# v2").unwrap();
+        assert!(!store.is_trusted(&project_file).unwrap());
+    }
+
+    #[test]
+    fn test_trust_is_pinned_to_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join(".dbjump.toml");
+        let shared = dir.path().join("shared.toml");
+        fs::write(&project_file, "include = [\"shared.toml\"]\n").unwrap();
+        fs::write(&shared, "include = [\"extra.toml\"]\n").unwrap();
+
+        let mut store = TrustStore::default();
+        store.trust(&project_file).unwrap();
+        assert!(store.is_trusted(&project_file).unwrap());
+
+        // Creating a missing nested include revokes trust too
+        fs::write(dir.path().join("extra.toml"), "").unwrap();
+        assert!(!store.is_trusted(&project_file).unwrap());
+
+        store.trust(&project_file).unwrap();
+        fs::write(&shared, "include = [\"extra.toml\"]\n# edited\n").unwrap();
+        assert!(!store.is_trusted(&project_file).unwrap());
+    }
+
+    #[test]
+    fn test_trust_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join(".dbjump.toml");
+        fs::write(&project_file, "").unwrap();
+        let store_path = dir.path().join("state").join(TRUST_FILE_NAME);
+
+        let mut store = TrustStore::default();
+        store.trust(&project_file).unwrap();
+        store.save_to(&store_path).unwrap();
+
+        let mut loaded = TrustStore::load_from(&store_path).unwrap();
+        assert!(loaded.is_trusted(&project_file).unwrap());
+        assert!(loaded.revoke(&project_file).unwrap());
+        assert!(!loaded.is_trusted(&project_file).unwrap());
+    }
+}
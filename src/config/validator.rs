@@ -1,59 +1,526 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 
-use crate::config::Config;
+use crate::config::diagnostic::locate;
+use crate::config::inherit::check_inheritance;
+use crate::config::parser::conf_d_dir;
+use crate::config::{
+    is_prod_environment, Config, DatabaseConfig, DatabaseEngine, Diagnostic, HostAddress,
+    HostStrategy, TlsConfig,
+};
+use crate::database::get_connector;
 use crate::error::{DbJumpError, Result};
 
+/// Fail with every error found. Warnings are not fatal.
 pub fn validate_config(config: &Config) -> Result<()> {
-    let mut aliases = HashSet::new();
+    let errors: Vec<Diagnostic> = check_config(config)
+        .into_iter()
+        .filter(Diagnostic::is_error)
+        .collect();
 
-    for db in &config.database {
-        // Check alias uniqueness
-        if !aliases.insert(&db.alias) {
-            return Err(DbJumpError::DuplicateAlias(db.alias.clone()));
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DbJumpError::InvalidConfig(errors))
+    }
+}
+
+/// Every problem found in the configuration, pointing at the offending
+/// line where the file can be located
+pub fn check_config(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = check_inheritance(config);
+    let mut aliases = HashMap::new();
+
+    for (index, db) in config.database.iter().enumerate() {
+        // Earlier entries with the same alias in the same file
+        let occurrence = config.database[..index]
+            .iter()
+            .filter(|other| other.alias == db.alias && other.source == db.source)
+            .count();
+        let error = |e: DbJumpError, field: &str| {
+            Diagnostic::error(e.to_string())
+                .alias(&db.alias)
+                .field(field)
+                .file(db.source.as_deref())
+                .occurrence(occurrence)
+        };
+
+        // Check alias uniqueness across all merged files
+        if let Some(first) = aliases.insert(db.alias.as_str(), db) {
+            diagnostics.push(error(
+                DbJumpError::DuplicateAlias(
+                    db.alias.clone(),
+                    first.source_display(),
+                    db.source_display(),
+                ),
+                "alias",
+            ));
         }
 
         // Check alias format (alphanumeric, hyphens, underscores only)
         if !is_valid_alias(&db.alias) {
-            return Err(DbJumpError::InvalidAliasFormat(db.alias.clone()));
+            diagnostics.push(error(
+                DbJumpError::InvalidAliasFormat(db.alias.clone()),
+                "alias",
+            ));
         }
 
         // Check optional fields if provided
-        if let Some(ref host) = db.host {
-            if host.is_empty() {
-                return Err(DbJumpError::MissingField(format!(
-                    "host for alias '{}' cannot be empty",
+        let fields = [
+            ("host", &db.host),
+            ("socket", &db.socket),
+            ("user", &db.user),
+            ("password", &db.password),
+        ];
+        for (field, value) in fields {
+            if value.as_deref() == Some("") {
+                diagnostics.push(error(
+                    DbJumpError::MissingField(format!(
+                        "{} for alias '{}' cannot be empty",
+                        field, db.alias
+                    )),
+                    field,
+                ));
+            }
+        }
+
+        // Port validation (1-65535) if provided
+        if db.port == Some(0) {
+            diagnostics.push(error(DbJumpError::InvalidPort(0), "port"));
+        }
+
+        if !db.hosts.is_empty() {
+            let conflicting = [("host", db.host.is_some()), ("socket", db.socket.is_some())];
+            for (field, _) in conflicting.into_iter().filter(|(_, is_set)| *is_set) {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "alias '{}' sets both hosts and {}; use one or the other",
+                        db.alias, field
+                    )),
+                    "hosts",
+                ));
+            }
+
+            for entry in &db.hosts {
+                if let Err(message) = HostAddress::parse(entry) {
+                    diagnostics.push(error(
+                        DbJumpError::ConfigError(format!(
+                            "invalid entry in hosts for alias '{}': {}",
+                            db.alias, message
+                        )),
+                        "hosts",
+                    ));
+                }
+            }
+
+            let connector = get_connector(&db.engine);
+            if db.host_strategy() == HostStrategy::EngineNative
+                && !connector.supports_multiple_hosts()
+            {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "{} cannot fail over between hosts by itself (alias '{}'); \
+                         use first-reachable or random",
+                        connector.cli_tool_name(),
+                        db.alias
+                    )),
+                    "host_strategy",
+                ));
+            }
+        }
+
+        if db.srv {
+            // SRV records supply the ports and the list of hosts
+            let conflicting = [
+                ("port", db.port.is_some()),
+                ("hosts", !db.hosts.is_empty()),
+                ("socket", db.socket.is_some()),
+            ];
+            for (field, _) in conflicting.into_iter().filter(|(_, is_set)| *is_set) {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "alias '{}' uses srv, which cannot be combined with {}",
+                        db.alias, field
+                    )),
+                    field,
+                ));
+            }
+            if db.host.is_none() {
+                diagnostics.push(error(
+                    DbJumpError::MissingField(format!(
+                        "host for alias '{}' is required with srv",
+                        db.alias
+                    )),
+                    "srv",
+                ));
+            }
+        }
+
+        let scalars = [("uri_options", &db.uri_options), ("settings", &db.settings)];
+        for (field, table) in scalars {
+            for (key, value) in table {
+                if matches!(value, toml::Value::Array(_) | toml::Value::Table(_)) {
+                    diagnostics.push(error(
+                        DbJumpError::ConfigError(format!(
+                            "{}.{} for alias '{}' must be a string, number or boolean",
+                            field, key, db.alias
+                        )),
+                        &format!("{}.{}", field, key),
+                    ));
+                }
+            }
+        }
+
+        // ClickHouse settings become XML element names and flags
+        if db.engine == DatabaseEngine::ClickHouse {
+            for key in db.settings.keys().filter(|key| !is_valid_setting_name(key)) {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "settings.{} for alias '{}' is not a valid setting name \
+                         (letters, digits and underscores only)",
+                        key, db.alias
+                    )),
+                    "settings",
+                ));
+            }
+        }
+
+        if db.read_only {
+            let connector = get_connector(&db.engine);
+            for key in db
+                .settings
+                .keys()
+                .filter(|key| connector.read_only_settings().contains(&key.as_str()))
+            {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "settings.{} for alias '{}' conflicts with read_only",
+                        key, db.alias
+                    )),
+                    &format!("settings.{}", key),
+                ));
+            }
+        }
+
+        if db.secure && db.tls.as_ref().is_some_and(TlsConfig::is_disabled) {
+            diagnostics.push(error(
+                DbJumpError::ConfigError(format!(
+                    "alias '{}' sets secure but its tls.mode is disable",
                     db.alias
-                )));
+                )),
+                "secure",
+            ));
+        }
+
+        if db.socket.is_some() {
+            // PostgreSQL names the socket file after the port, so it still applies
+            let conflicting = [
+                ("host", db.host.is_some()),
+                (
+                    "port",
+                    db.port.is_some() && db.engine != DatabaseEngine::PostgreSQL,
+                ),
+            ];
+            for (field, _) in conflicting.into_iter().filter(|(_, is_set)| *is_set) {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "alias '{}' sets both socket and {}; use one or the other",
+                        db.alias, field
+                    )),
+                    "socket",
+                ));
+            }
+
+            let connector = get_connector(&db.engine);
+            if !connector.supports_socket() {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "{} cannot connect over a Unix socket (alias '{}')",
+                        connector.cli_tool_name(),
+                        db.alias
+                    )),
+                    "socket",
+                ));
             }
         }
 
-        if let Some(ref user) = db.user {
-            if user.is_empty() {
-                return Err(DbJumpError::MissingField(format!(
-                    "user for alias '{}' cannot be empty",
+        if let Some(ref tls) = db.tls {
+            for (field, path) in tls.files() {
+                if !path.is_file() {
+                    diagnostics.push(error(
+                        DbJumpError::ConfigError(format!(
+                            "tls.{} for alias '{}' does not exist: {}",
+                            field,
+                            db.alias,
+                            path.display()
+                        )),
+                        &format!("tls.{}", field),
+                    ));
+                }
+            }
+
+            // libpq can only check a different name when given an address
+            let numeric_host = db
+                .host
+                .as_deref()
+                .is_some_and(|host| host.parse::<IpAddr>().is_ok());
+            if db.engine == DatabaseEngine::PostgreSQL && tls.server_name.is_some() && !numeric_host
+            {
+                diagnostics.push(error(
+                    DbJumpError::ConfigError(format!(
+                        "tls.server_name for alias '{}' requires host to be an IP address",
+                        db.alias
+                    )),
+                    "tls.server_name",
+                ));
+            }
+        }
+    }
+
+    diagnostics.extend(check_security(config));
+
+    locate(&mut diagnostics, config.pending());
+    diagnostics
+}
+
+/// Non-fatal warnings about settings that leak credentials or traffic
+fn check_security(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = check_permissions(config);
+
+    for db in &config.database {
+        let warning = |message: String, field: &str| {
+            Diagnostic::warning(message)
+                .alias(&db.alias)
+                .field(field)
+                .file(db.source.as_deref())
+        };
+
+        let is_prod =
+            db.has_tag("prod") || db.environment.as_deref().is_some_and(is_prod_environment);
+        if is_prod && db.password.is_some() {
+            diagnostics.push(warning(
+                format!(
+                    "alias '{}' is prod but stores its password in plain text; \
+                     consider moving it to a separate 0600 include file",
                     db.alias
-                )));
+                ),
+                "password",
+            ));
+        }
+
+        if let Some((setting, field)) = tls_disabling_setting(db) {
+            let remote: Vec<_> = db
+                .host_names()
+                .into_iter()
+                .filter(|host| !is_local_host(host))
+                .map(|host| format!("'{}'", host))
+                .collect();
+            if !remote.is_empty() {
+                diagnostics.push(warning(
+                    format!(
+                        "'{}' disables TLS for remote host{} {}",
+                        setting,
+                        if remote.len() > 1 { "s" } else { "" },
+                        remote.join(", ")
+                    ),
+                    field,
+                ));
             }
         }
 
-        if let Some(ref password) = db.password {
-            if password.is_empty() {
-                return Err(DbJumpError::MissingField(format!(
-                    "password for alias '{}' cannot be empty",
+        use DatabaseEngine::{ClickHouse, MongoDB, MySQL, PostgreSQL};
+        let engine_specific: [(&str, bool, &[DatabaseEngine]); 14] = [
+            ("replica_set", db.replica_set.is_some(), &[MongoDB]),
+            ("srv", db.srv, &[MongoDB]),
+            ("auth_source", db.auth_source.is_some(), &[MongoDB]),
+            ("auth_mechanism", db.auth_mechanism.is_some(), &[MongoDB]),
+            ("uri_options", !db.uri_options.is_empty(), &[MongoDB]),
+            ("secure", db.secure, &[ClickHouse]),
+            (
+                "settings",
+                !db.settings.is_empty(),
+                &[ClickHouse, PostgreSQL],
+            ),
+            ("service", db.service.is_some(), &[PostgreSQL]),
+            (
+                "application_name",
+                db.application_name.is_some(),
+                &[PostgreSQL],
+            ),
+            ("search_path", db.search_path.is_some(), &[PostgreSQL]),
+            ("role", db.role.is_some(), &[PostgreSQL]),
+            (
+                "statement_timeout",
+                db.statement_timeout.is_some(),
+                &[PostgreSQL],
+            ),
+            ("charset", db.charset.is_some(), &[MySQL]),
+            ("init_command", db.init_command.is_some(), &[MySQL]),
+        ];
+        for (field, _, engines) in engine_specific
+            .into_iter()
+            .filter(|(_, is_set, engines)| *is_set && !engines.contains(&db.engine))
+        {
+            let names: Vec<_> = engines.iter().map(|e| format!("{:?}", e)).collect();
+            diagnostics.push(warning(
+                format!(
+                    "{} only applies to {} and is ignored for '{}'",
+                    field,
+                    names.join(" and "),
                     db.alias
-                )));
+                ),
+                field,
+            ));
+        }
+
+        for key in db.uri_options.keys() {
+            if let Some((_, field)) = URI_OPTION_FIELDS.iter().find(|(option, _)| option == key) {
+                diagnostics.push(warning(
+                    format!(
+                        "uri option '{}' duplicates the '{}' field; set '{}' instead",
+                        key, field, field
+                    ),
+                    "uri_options",
+                ));
             }
         }
 
-        // Port validation (1-65535) if provided
-        if let Some(port) = db.port {
-            if port == 0 {
-                return Err(DbJumpError::InvalidPort(port));
+        let connector = get_connector(&db.engine);
+        if let Some(ref tls) = db.tls {
+            let set = [
+                ("key_file", tls.key_file.is_some()),
+                ("server_name", tls.server_name.is_some()),
+            ];
+            for (field, _) in set.into_iter().filter(|(field, is_set)| {
+                *is_set && connector.unsupported_tls_fields().contains(field)
+            }) {
+                diagnostics.push(warning(
+                    format!(
+                        "tls.{} is not supported by {} and is ignored",
+                        field,
+                        connector.cli_tool_name()
+                    ),
+                    &format!("tls.{}", field),
+                ));
+            }
+        }
+
+        for option in &db.options {
+            let flag = option
+                .split_once('=')
+                .map_or(option.as_str(), |(flag, _)| flag);
+            if let Some((_, field)) = connector.field_flags().iter().find(|(f, _)| *f == flag) {
+                diagnostics.push(warning(
+                    format!(
+                        "option '{}' duplicates the '{}' field; set '{}' instead",
+                        flag, field, field
+                    ),
+                    "options",
+                ));
             }
         }
     }
 
-    Ok(())
+    diagnostics
+}
+
+/// MongoDB URI options that have a typed field, as (option, field)
+const URI_OPTION_FIELDS: &[(&str, &str)] = &[
+    ("replicaSet", "replica_set"),
+    ("authSource", "auth_source"),
+    ("authMechanism", "auth_mechanism"),
+    ("tls", "tls"),
+    ("ssl", "tls"),
+    ("tlsCAFile", "tls.ca_file"),
+    ("tlsCertificateKeyFile", "tls.cert_file"),
+];
+
+/// Config files and dbjump's own config directories should be private, as
+/// `dbjump init` and the editing commands leave them. Other directories
+/// holding included files, such as the home directory, are left alone.
+fn check_permissions(config: &Config) -> Vec<Diagnostic> {
+    let mut files = BTreeSet::new();
+    for source in config.database.iter().filter_map(|db| db.source.as_deref()) {
+        files.insert(source.to_path_buf());
+    }
+    for source in config.template.iter().filter_map(|t| t.source.as_deref()) {
+        files.insert(source.to_path_buf());
+    }
+
+    let dirs: Vec<PathBuf> = config
+        .config_dir
+        .iter()
+        .flat_map(|dir| [dir.clone(), conf_d_dir(dir)])
+        .filter(|dir| dir.is_dir())
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    for (path, expected) in files
+        .iter()
+        .map(|file| (file, "600"))
+        .chain(dirs.iter().map(|dir| (dir, "700")))
+    {
+        if let Some(mode) = group_or_other_mode(path) {
+            diagnostics.push(
+                Diagnostic::warning(format!(
+                    "{} is accessible by other users (mode {:o}); run chmod {} on it",
+                    path.display(),
+                    mode,
+                    expected
+                ))
+                .file(Some(path)),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(unix)]
+fn group_or_other_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    (mode & 0o077 != 0).then_some(mode)
+}
+
+#[cfg(not(unix))]
+fn group_or_other_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// A setting that turns TLS off and the field it is in, if any
+fn tls_disabling_setting(db: &DatabaseConfig) -> Option<(&str, &'static str)> {
+    const DISABLING: &[&str] = &[
+        "--ssl-mode=disabled",
+        "--skip-ssl",
+        "--ssl=0",
+        "sslmode=disable",
+        "ssl=false",
+        "tls=false",
+    ];
+
+    if db.tls.as_ref().is_some_and(TlsConfig::is_disabled) {
+        return Some(("tls.mode = \"disable\"", "tls.mode"));
+    }
+
+    db.options
+        .iter()
+        .find(|option| {
+            let option = option.to_lowercase();
+            DISABLING.iter().any(|disabling| option.contains(disabling))
+        })
+        .map(|option| (option.as_str(), "options"))
+}
+
+fn is_local_host(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "::1") || host.starts_with('/')
+}
+
+fn is_valid_setting_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
 fn is_valid_alias(alias: &str) -> bool {
@@ -70,14 +537,11 @@ mod tests {
 
     fn create_test_config(alias: &str) -> DatabaseConfig {
         DatabaseConfig {
-            alias: alias.to_string(),
-            engine: DatabaseEngine::ClickHouse,
             host: Some("localhost".to_string()),
             port: Some(9000),
             user: Some("user".to_string()),
             password: Some("pass".to_string()),
-            database: None,
-            options: vec![],
+            ..DatabaseConfig::new(alias, DatabaseEngine::ClickHouse)
         }
     }
 
@@ -100,11 +564,382 @@ mod tests {
     fn test_duplicate_alias_detection() {
         let config = Config {
             database: vec![create_test_config("db1"), create_test_config("db1")],
+            ..Default::default()
         };
 
         assert!(matches!(
             validate_config(&config),
-            Err(DbJumpError::DuplicateAlias(_))
+            Err(DbJumpError::InvalidConfig(diagnostics))
+                if diagnostics[0].message.starts_with("Duplicate alias 'db1'")
         ));
     }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let mut bad_port = create_test_config("db2");
+        bad_port.port = Some(0);
+        let config = Config {
+            database: vec![
+                create_test_config("db 1"),
+                bad_port,
+                create_test_config("db2"),
+            ],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        let fields: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.alias.as_deref().unwrap(), d.field.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![("db 1", "alias"), ("db2", "port"), ("db2", "alias")]
+        );
+    }
+
+    #[test]
+    fn test_security_warnings() {
+        let db = DatabaseConfig {
+            host: Some("mongo.internal".to_string()),
+            password: Some("secret".to_string()),
+            environment: Some("prod".to_string()),
+            options: vec!["--port=27018".to_string(), "--tls=false".to_string()],
+            ..DatabaseConfig::new("prod-mongo", DatabaseEngine::MongoDB)
+        };
+        let config = Config {
+            database: vec![db],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        assert!(diagnostics.iter().all(|d| !d.is_error()));
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 4, "{:?}", messages);
+        assert!(messages[0].contains("plain text"));
+        assert!(messages[1].contains("disables TLS"));
+        assert!(messages[2].contains("duplicates the 'port' field"));
+        assert!(messages[3].contains("duplicates the 'tls' field"));
+        assert!(validate_config(&config).is_ok());
+
+        let db = DatabaseConfig {
+            hosts: vec!["localhost:5432".to_string(), "pg2:5432".to_string()],
+            tls: Some(TlsConfig {
+                mode: Some(crate::config::TlsMode::Disable),
+                ..Default::default()
+            }),
+            ..DatabaseConfig::new("cluster", DatabaseEngine::PostgreSQL)
+        };
+        let config = Config {
+            database: vec![db],
+            ..Default::default()
+        };
+        let diagnostics = check_config(&config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "'tls.mode = \"disable\"' disables TLS for remote host 'pg2'"
+        );
+    }
+
+    #[test]
+    fn test_socket_conflicts() {
+        let socket = Some("/var/run/db.sock".to_string());
+        let config = Config {
+            database: vec![
+                DatabaseConfig {
+                    socket: socket.clone(),
+                    port: Some(5433),
+                    ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+                },
+                DatabaseConfig {
+                    socket: socket.clone(),
+                    host: Some("db".to_string()),
+                    port: Some(3306),
+                    ..DatabaseConfig::new("mysql", DatabaseEngine::MySQL)
+                },
+                DatabaseConfig {
+                    socket,
+                    ..DatabaseConfig::new("ch", DatabaseEngine::ClickHouse)
+                },
+            ],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages[0].contains("'mysql' sets both socket and host"));
+        assert!(messages[1].contains("'mysql' sets both socket and port"));
+        assert!(messages[2].contains("clickhouse cannot connect over a Unix socket"));
+        // PostgreSQL keeps the port, which names the socket file
+        assert!(!diagnostics.iter().any(|d| d.alias.as_deref() == Some("pg")));
+
+        let config = Config {
+            database: vec![DatabaseConfig {
+                socket: Some("/var/run/postgresql".to_string()),
+                host: Some("db".to_string()),
+                ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+            }],
+            ..Default::default()
+        };
+        let diagnostics = check_config(&config);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .contains("'pg' sets both socket and host"));
+    }
+
+    #[test]
+    fn test_hosts_checks() {
+        let config = Config {
+            database: vec![
+                DatabaseConfig {
+                    hosts: vec!["db1:3306".to_string(), "db2:x".to_string()],
+                    host: Some("db".to_string()),
+                    host_strategy: Some(HostStrategy::EngineNative),
+                    ..DatabaseConfig::new("mysql", DatabaseEngine::MySQL)
+                },
+                DatabaseConfig {
+                    hosts: vec!["pg1".to_string(), "pg2".to_string()],
+                    host_strategy: Some(HostStrategy::EngineNative),
+                    ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+                },
+            ],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages[0].contains("sets both hosts and host"));
+        assert!(messages[1].contains("'db2:x' has an invalid port"));
+        assert!(messages[2].contains("mysql cannot fail over"));
+    }
+
+    #[test]
+    fn test_mongodb_options() {
+        let config = Config {
+            database: vec![
+                DatabaseConfig {
+                    srv: true,
+                    port: Some(27017),
+                    uri_options: [
+                        ("authSource".to_string(), toml::Value::from("admin")),
+                        ("w".to_string(), toml::Value::Array(Vec::new())),
+                    ]
+                    .into_iter()
+                    .collect(),
+                    ..DatabaseConfig::new("atlas", DatabaseEngine::MongoDB)
+                },
+                DatabaseConfig {
+                    auth_source: Some("admin".to_string()),
+                    ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+                },
+            ],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.is_error(), d.field.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (true, "port"),
+                (true, "srv"),
+                (true, "uri_options.w"),
+                (false, "uri_options"),
+                (false, "auth_source"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clickhouse_options() {
+        let config = Config {
+            database: vec![
+                DatabaseConfig {
+                    secure: true,
+                    tls: Some(TlsConfig {
+                        mode: Some(crate::config::TlsMode::Disable),
+                        ..Default::default()
+                    }),
+                    read_only: true,
+                    settings: [
+                        ("max_threads".to_string(), toml::Value::Array(Vec::new())),
+                        ("readonly".to_string(), toml::Value::Integer(0)),
+                        ("a><b".to_string(), toml::Value::Integer(1)),
+                    ]
+                    .into_iter()
+                    .collect(),
+                    ..DatabaseConfig::new("ch", DatabaseEngine::ClickHouse)
+                },
+                DatabaseConfig {
+                    secure: true,
+                    ..DatabaseConfig::new("mysql", DatabaseEngine::MySQL)
+                },
+            ],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.is_error(), d.field.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (true, "settings.max_threads"),
+                (true, "settings"),
+                (true, "settings.readonly"),
+                (true, "secure"),
+                (false, "secure"),
+            ]
+        );
+        assert!(diagnostics[4]
+            .message
+            .contains("only applies to ClickHouse"));
+    }
+
+    #[test]
+    fn test_read_only_setting_conflicts() {
+        let config = Config {
+            database: vec![DatabaseConfig {
+                read_only: true,
+                settings: [
+                    (
+                        "default_transaction_read_only".to_string(),
+                        toml::Value::from("off"),
+                    ),
+                    ("work_mem".to_string(), toml::Value::from("64MB")),
+                ]
+                .into_iter()
+                .collect(),
+                ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+            }],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(
+            diagnostics[0].field.as_deref(),
+            Some("settings.default_transaction_read_only")
+        );
+    }
+
+    #[test]
+    fn test_tls_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca_file = dir.path().join("ca.pem");
+        fs::write(&ca_file, "").unwrap();
+
+        let mongo = DatabaseConfig {
+            host: Some("mongo.internal".to_string()),
+            tls: Some(TlsConfig {
+                ca_file: Some(ca_file),
+                key_file: Some(dir.path().join("missing.pem")),
+                ..Default::default()
+            }),
+            ..DatabaseConfig::new("mongo", DatabaseEngine::MongoDB)
+        };
+        let pg = DatabaseConfig {
+            host: Some("pg.internal".to_string()),
+            tls: Some(TlsConfig {
+                mode: Some(crate::config::TlsMode::Disable),
+                server_name: Some("pg".to_string()),
+                ..Default::default()
+            }),
+            ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+        };
+        let config = Config {
+            database: vec![mongo, pg],
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                (
+                    d.is_error(),
+                    d.alias.as_deref().unwrap(),
+                    d.field.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (true, "mongo", "tls.key_file"),
+                (true, "pg", "tls.server_name"),
+                (false, "mongo", "tls.key_file"),
+                (false, "pg", "tls.mode"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permission_warnings() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o700)).unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        // An included file in a shared directory, like the home directory
+        let shared = tempfile::tempdir().unwrap();
+        fs::set_permissions(shared.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        let include = shared.path().join("secrets.toml");
+        fs::write(&include, "").unwrap();
+        fs::set_permissions(&include, fs::Permissions::from_mode(0o600)).unwrap();
+
+        let mut db = create_test_config("db1");
+        db.source = Some(path.clone());
+        let mut included = create_test_config("db2");
+        included.source = Some(include);
+        let config = Config {
+            database: vec![db, included],
+            config_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let diagnostics = check_config(&config);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("mode 644"));
+        assert_eq!(diagnostics[0].file.as_deref(), Some(path.as_path()));
+
+        // dbjump's own directory and its conf.d are checked
+        let conf_d = dir.path().join("conf.d");
+        fs::create_dir(&conf_d).unwrap();
+        fs::set_permissions(&conf_d, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        let diagnostics = check_config(&config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file.as_deref(), Some(conf_d.as_path()));
+    }
+
+    #[test]
+    fn test_duplicate_alias_reports_both_sources() {
+        let mut first = create_test_config("db1");
+        first.source = Some("/etc/team.toml".into());
+        let mut second = create_test_config("db1");
+        second.source = Some("/home/me/conf.d/private.toml".into());
+        let config = Config {
+            database: vec![first, second],
+            ..Default::default()
+        };
+
+        let message = validate_config(&config).unwrap_err().to_string();
+        assert!(message.contains("/etc/team.toml"));
+        assert!(message.contains("/home/me/conf.d/private.toml"));
+    }
 }
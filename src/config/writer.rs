@@ -0,0 +1,334 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use toml_edit::{value, ArrayOfTables, DocumentMut, Item, Table};
+
+use crate::config::DatabaseConfig;
+use crate::error::{DbJumpError, Result};
+
+/// A single config file opened for editing. Comments and formatting of
+/// everything that isn't touched are preserved.
+pub struct ConfigFile {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl ConfigFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(path, &content)
+    }
+
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        let doc = content
+            .parse::<DocumentMut>()
+            .map_err(|e| DbJumpError::ConfigParseError(format!("{}: {}", path.display(), e)))?;
+        Ok(ConfigFile {
+            path: path.to_path_buf(),
+            doc,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file contents as they would be written
+    pub fn contents(&self) -> String {
+        self.doc.to_string()
+    }
+
+    /// Append a `[[database]]` entry
+    pub fn add_database(&mut self, table: Table) -> Result<()> {
+        self.databases_mut()?.push(table);
+        Ok(())
+    }
+
+    pub fn remove_database(&mut self, alias: &str) -> Result<()> {
+        let index = self.position(alias)?;
+        self.databases_mut()?.remove(index);
+        Ok(())
+    }
+
+    /// Rename an alias, updating `extends` references to it in this file
+    pub fn rename_database(&mut self, alias: &str, new_alias: &str) -> Result<()> {
+        let index = self.position(alias)?;
+        let databases = self.databases_mut()?;
+        if let Some(table) = databases.get_mut(index) {
+            replace_str(table, "alias", new_alias);
+        }
+
+        for key in ["database", "template"] {
+            let Some(tables) = self.doc.get_mut(key).and_then(Item::as_array_of_tables_mut) else {
+                continue;
+            };
+            for table in tables.iter_mut() {
+                if table.get("extends").and_then(Item::as_str) == Some(alias) {
+                    replace_str(table, "extends", new_alias);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append a copy of an alias's entry under a new name
+    pub fn copy_database(&mut self, alias: &str, new_alias: &str) -> Result<()> {
+        let index = self.position(alias)?;
+        let mut table = self.doc["database"][index]
+            .as_table()
+            .cloned()
+            .ok_or_else(|| alias_not_in_file(alias, &self.path))?;
+        // Comments above the original belong to it, not the copy
+        table.decor_mut().clear();
+        replace_str(&mut table, "alias", new_alias);
+        self.databases_mut()?.push(table);
+        Ok(())
+    }
+
+    /// Replace the file atomically, keeping it readable only by the owner
+    pub fn save(&self) -> Result<()> {
+        write_config_file(&self.path, &self.contents())
+    }
+
+    fn position(&self, alias: &str) -> Result<usize> {
+        self.doc
+            .get("database")
+            .and_then(Item::as_array_of_tables)
+            .and_then(|tables| {
+                tables
+                    .iter()
+                    .position(|table| table.get("alias").and_then(Item::as_str) == Some(alias))
+            })
+            .ok_or_else(|| alias_not_in_file(alias, &self.path))
+    }
+
+    fn databases_mut(&mut self) -> Result<&mut ArrayOfTables> {
+        self.doc
+            .entry("database")
+            .or_insert_with(|| Item::ArrayOfTables(ArrayOfTables::new()))
+            .as_array_of_tables_mut()
+            .ok_or_else(|| {
+                DbJumpError::ConfigError(format!(
+                    "{}: 'database' is not an array of tables",
+                    self.path.display()
+                ))
+            })
+    }
+}
+
+/// Replace a config file atomically with `content`, readable only by the owner.
+/// A symlinked config stays a symlink: the file it points to is replaced.
+pub fn write_config_file(path: &Path, content: &str) -> Result<()> {
+    let path = match fs::canonicalize(path) {
+        Ok(target) => target,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => path.to_path_buf(),
+        Err(e) => return Err(e.into()),
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = dir.join(format!(".{}.tmp", file_name));
+
+    if let Err(e) = write_private_file(&temp_path, content) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    if let Err(e) = fs::rename(&temp_path, &path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// Create `path` afresh with owner-only permissions from the start, so the
+/// content is never readable by others, even briefly
+pub fn write_private_file(path: &Path, content: &str) -> Result<()> {
+    // A leftover from an interrupted write may have looser permissions
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(content.as_bytes())?;
+
+    Ok(())
+}
+
+/// Set a string value, keeping the whitespace and comments around the old one
+fn replace_str(table: &mut Table, key: &str, new_value: &str) {
+    let mut new_value = toml_edit::Value::from(new_value);
+    if let Some(old) = table.get(key).and_then(Item::as_value) {
+        *new_value.decor_mut() = old.decor().clone();
+    }
+    table[key] = Item::Value(new_value);
+}
+
+/// A `[[database]]` table holding the fields of `db` that are set
+pub fn database_table(db: &DatabaseConfig) -> Table {
+    let mut table = Table::new();
+    table["alias"] = value(&db.alias);
+    if let Ok(toml::Value::String(engine)) = toml::Value::try_from(&db.engine) {
+        table["engine"] = value(engine);
+    }
+
+    let strings = [
+        ("extends", &db.extends),
+        ("host", &db.host),
+        ("socket", &db.socket),
+        ("user", &db.user),
+        ("password", &db.password),
+        ("database", &db.database),
+        ("replica_set", &db.replica_set),
+        ("auth_source", &db.auth_source),
+        ("auth_mechanism", &db.auth_mechanism),
+        ("service", &db.service),
+        ("application_name", &db.application_name),
+        ("search_path", &db.search_path),
+        ("role", &db.role),
+        ("statement_timeout", &db.statement_timeout),
+        ("charset", &db.charset),
+        ("init_command", &db.init_command),
+        ("environment", &db.environment),
+    ];
+    for (key, field) in strings {
+        if let Some(field) = field {
+            table[key] = value(field);
+        }
+    }
+    if !db.hosts.is_empty() {
+        table["hosts"] = value(db.hosts.iter().collect::<toml_edit::Array>());
+    }
+    if let Some(port) = db.port {
+        table["port"] = value(i64::from(port));
+    }
+    if !db.tags.is_empty() {
+        table["tags"] = value(db.tags.iter().collect::<toml_edit::Array>());
+    }
+    if !db.options.is_empty() {
+        table["options"] = value(db.options.iter().collect::<toml_edit::Array>());
+    }
+
+    table
+}
+
+fn alias_not_in_file(alias: &str, path: &Path) -> DbJumpError {
+    DbJumpError::ConfigError(format!(
+        "alias '{}' is not defined in {}",
+        alias,
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"# Team databases
+
+# Primary warehouse
+[[database]]
+alias = "prod-ch"  # do not rename
+engine = "clickhouse"
+host = "ch.internal"
+
+[[database]]
+alias = "prod-ch-replica"
+extends = "prod-ch"
+host = "ch-replica.internal"
+"#;
+
+    fn open() -> ConfigFile {
+        ConfigFile::parse(Path::new("config.toml"), CONFIG).unwrap()
+    }
+
+    #[test]
+    fn test_rename_updates_extends_and_keeps_comments() {
+        let mut file = open();
+        file.rename_database("prod-ch", "prod-warehouse").unwrap();
+
+        let contents = file.contents();
+        assert!(contents.starts_with("# Team databases\n\n# Primary warehouse\n"));
+        assert!(contents.contains("alias = \"prod-warehouse\"  # do not rename"));
+        assert!(contents.contains("extends = \"prod-warehouse\""));
+        assert!(!contents.contains("\"prod-ch\""));
+    }
+
+    #[test]
+    fn test_copy_and_remove() {
+        let mut file = open();
+        file.copy_database("prod-ch", "staging-ch").unwrap();
+        file.remove_database("prod-ch-replica").unwrap();
+
+        let contents = file.contents();
+        assert_eq!(contents.matches("# Primary warehouse").count(), 1);
+        assert!(contents.contains("alias = \"staging-ch\""));
+        assert!(!contents.contains("prod-ch-replica"));
+        assert!(file.remove_database("missing").is_err());
+    }
+
+    #[test]
+    fn test_database_table_skips_unset_fields() {
+        let db = DatabaseConfig {
+            port: Some(5432),
+            tags: vec!["prod".to_string()],
+            ..DatabaseConfig::new("pg", crate::config::DatabaseEngine::PostgreSQL)
+        };
+        assert_eq!(
+            database_table(&db).to_string(),
+            "alias = \"pg\"\nengine = \"postgresql\"\nport = 5432\ntags = [\"prod\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_add_to_empty_file_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "# empty\n").unwrap();
+
+        let mut file = ConfigFile::open(&path).unwrap();
+        let mut table = Table::new();
+        table["alias"] = value("dev");
+        table["engine"] = value("postgresql");
+        file.add_database(table).unwrap();
+        file.save().unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("[[database]]\nalias = \"dev\"\nengine = \"postgresql\""));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_keeps_symlinked_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = dir.path().join("shared.toml");
+        let link = dir.path().join("config.toml");
+        fs::write(&shared, "# v1\n").unwrap();
+        std::os::unix::fs::symlink(&shared, &link).unwrap();
+
+        write_config_file(&link, "# v2\n").unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&shared).unwrap(), "# v2\n");
+    }
+}
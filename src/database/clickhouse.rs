@@ -1,23 +1,251 @@
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::config::DatabaseConfig;
+use crate::config::path::get_state_dir;
+use crate::config::writer::write_config_file;
+use crate::config::{DatabaseConfig, TlsConfig, TlsMode};
+use crate::database::prompt::PromptLabel;
 use crate::database::types::DatabaseConnector;
-use crate::error::Result;
+use crate::error::{DbJumpError, Result};
+use crate::utils::{expand_tilde, scalar_to_string, set_permissions_700};
+
+const CLIENT_CONFIG_DIR_NAME: &str = "clickhouse";
+/// Client shipped on its own by some packages, without the multi-call binary
+const STANDALONE_CLIENT: &str = "clickhouse-client";
+/// Where clickhouse client looks for its own config, in order, when not
+/// given --config-file
+const USER_CONFIG_FILES: [&str; 3] = [
+    "./clickhouse-client",
+    "~/.clickhouse-client/config",
+    "/etc/clickhouse-client/config",
+];
+const CONFIG_EXTENSIONS: [&str; 3] = ["xml", "yaml", "yml"];
 
 pub struct ClickHouseConnector;
 
-impl DatabaseConnector for ClickHouseConnector {
-    fn build_command(&self, config: &DatabaseConfig) -> Result<Command> {
+/// Whether to run the standalone client, because only that is installed
+fn uses_standalone_client() -> bool {
+    which::which("clickhouse").is_err() && which::which(STANDALONE_CLIENT).is_ok()
+}
+
+/// `clickhouse client`, or `clickhouse-client` where only that is installed
+fn client_command(standalone: bool) -> Command {
+    if standalone {
+        Command::new(STANDALONE_CLIENT)
+    } else {
         let mut cmd = Command::new("clickhouse");
         cmd.arg("client");
+        cmd
+    }
+}
+
+/// `secure = true` or a `tls` table that doesn't disable TLS
+fn is_secure(config: &DatabaseConfig) -> bool {
+    match config.tls {
+        Some(ref tls) => !tls.is_disabled(),
+        None => config.secure,
+    }
+}
+
+/// The `<openSSL>` section for `tls`, or None when `--secure` with the
+/// default verification is all it takes
+fn tls_client_config(tls: &TlsConfig) -> Option<String> {
+    let verification = match tls.mode() {
+        TlsMode::Disable | TlsMode::VerifyFull => None,
+        TlsMode::Require => Some("none"),
+        TlsMode::VerifyCa => Some("relaxed"),
+    };
+    if verification.is_none() && tls.files().is_empty() {
+        return None;
+    }
+
+    let mut client = String::new();
+    if let Some(verification) = verification {
+        client.push_str(&format!(
+            "            <verificationMode>{}</verificationMode>\n",
+            verification
+        ));
+    }
+    for (field, path) in tls.files() {
+        let element = match field {
+            "ca_file" => "caConfig",
+            "cert_file" => "certificateFile",
+            _ => "privateKeyFile",
+        };
+        client.push_str(&format!(
+            "            <{0}>{1}</{0}>\n",
+            element,
+            xml_escape(&path.to_string_lossy())
+        ));
+    }
+
+    Some(format!(
+        "    <openSSL>\n        <client>\n{}        </client>\n    </openSSL>\n",
+        client
+    ))
+}
+
+/// The alias's `settings`, less a `readonly` that `read_only` overrides
+fn applied_settings(config: &DatabaseConfig) -> impl Iterator<Item = (&String, &toml::Value)> {
+    config
+        .settings
+        .iter()
+        .filter(|(name, _)| !(config.read_only && name.as_str() == "readonly"))
+}
+
+/// Client config for the alias's `settings` and `tls`, under the root
+/// element `root`, or None when the client's own config is enough
+fn client_config_fragment(config: &DatabaseConfig, root: &str) -> Option<String> {
+    let mut body = String::new();
+    for (name, value) in applied_settings(config) {
+        body.push_str(&format!(
+            "    <{0}>{1}</{0}>\n",
+            name,
+            xml_escape(&scalar_to_string(value))
+        ));
+    }
+    let tls = config.tls.as_ref().filter(|tls| !tls.is_disabled());
+    if let Some(openssl) = tls.and_then(tls_client_config) {
+        body.push_str(&openssl);
+    }
+
+    if body.is_empty() {
+        None
+    } else {
+        Some(format!("<{0}>\n{1}</{0}>\n", root, body))
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The config file clickhouse client would read by itself
+fn user_client_config() -> Option<PathBuf> {
+    USER_CONFIG_FILES.iter().find_map(|base| {
+        CONFIG_EXTENSIONS
+            .iter()
+            .map(|ext| expand_tilde(&format!("{}.{}", base, ext)))
+            .find(|path| path.is_file())
+    })
+}
+
+/// Name of the root element of a client config. YAML configs always get
+/// `clickhouse`; XML ones may also use `config` or `yandex`.
+fn config_root(path: &Path) -> String {
+    let is_xml = path.extension().is_some_and(|ext| ext == "xml");
+    let content = if is_xml {
+        fs::read_to_string(path).ok()
+    } else {
+        None
+    };
+    content
+        .as_deref()
+        .and_then(xml_root)
+        .unwrap_or("clickhouse")
+        .to_string()
+}
+
+/// First element name in an XML document, past the declaration and comments
+fn xml_root(content: &str) -> Option<&str> {
+    let mut rest = content;
+    loop {
+        rest = &rest[rest.find('<')? + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = &comment[comment.find("-->")? + 3..];
+        } else if rest.starts_with('?') || rest.starts_with('!') {
+            rest = &rest[rest.find('>')? + 1..];
+        } else {
+            let end = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+            return Some(&rest[..end]);
+        }
+    }
+}
+
+/// Write the config for `alias` under `dir` and return the file to pass as
+/// --config-file. The file itself stands in for the user's own config (a
+/// symlink to it, or an empty one), and `fragment` goes in the `<alias>.d`
+/// directory next to it, which clickhouse client merges on top. Nothing in
+/// it is secret, so it is simply rewritten on every connection.
+fn write_client_config(
+    dir: &Path,
+    alias: &str,
+    user_config: Option<&Path>,
+    fragment: &str,
+) -> Result<PathBuf> {
+    let fragment_dir = dir.join(format!("{}.d", alias));
+    fs::create_dir_all(&fragment_dir)?;
+    set_permissions_700(dir)?;
+    write_config_file(&fragment_dir.join("dbjump.xml"), fragment)?;
+
+    let extension = user_config
+        .and_then(Path::extension)
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("xml");
+    let path = dir.join(format!("{}.{}", alias, extension));
+    match fs::symlink_metadata(&path) {
+        Ok(_) => fs::remove_file(&path)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    match user_config {
+        Some(user_config) => link_user_config(user_config, &path)?,
+        None => write_config_file(&path, "<clickhouse>\n</clickhouse>\n")?,
+    }
+
+    Ok(path)
+}
+
+#[cfg(unix)]
+fn link_user_config(user_config: &Path, path: &Path) -> Result<()> {
+    let target = fs::canonicalize(user_config)?;
+    std::os::unix::fs::symlink(target, path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn link_user_config(user_config: &Path, path: &Path) -> Result<()> {
+    fs::copy(user_config, path)?;
+    Ok(())
+}
+
+impl ClickHouseConnector {
+    /// The command for `config`, given which client binary to run and the
+    /// generated config file, if any
+    fn command_for(
+        &self,
+        config: &DatabaseConfig,
+        standalone: bool,
+        config_file: Option<&Path>,
+    ) -> Command {
+        let mut cmd = client_command(standalone);
+
+        if let Some(path) = config_file {
+            cmd.arg("--config-file").arg(path);
+        }
 
         // Optional connection parameters (only add if specified)
         if let Some(ref host) = config.host {
             cmd.arg("-h").arg(host);
         }
 
-        if let Some(port) = config.port {
-            cmd.arg("--port").arg(port.to_string());
+        let addresses = config.host_addresses();
+        if addresses.is_empty() {
+            if let Some(port) = config.port {
+                cmd.arg("--port").arg(port.to_string());
+            }
+        } else {
+            // clickhouse client tries each --host in turn, pairing each with
+            // the --port that follows it
+            for address in &addresses {
+                cmd.arg("--host").arg(&address.host);
+                if let Some(port) = address.port.or(config.port) {
+                    cmd.arg("--port").arg(port.to_string());
+                }
+            }
         }
 
         if let Some(ref user) = config.user {
@@ -33,17 +261,107 @@ impl DatabaseConnector for ClickHouseConnector {
             cmd.arg("--database").arg(database);
         }
 
+        if is_secure(config) {
+            cmd.arg("--secure");
+        }
+
+        // Also in the generated config, but flags win over anything the
+        // user's own config sets
+        for (name, value) in applied_settings(config) {
+            cmd.arg(format!("--{}={}", name, scalar_to_string(value)));
+        }
+
+        if config.read_only {
+            cmd.arg("--readonly=1");
+        }
+
+        if let Some(label) = PromptLabel::from_config(config) {
+            cmd.arg("--prompt")
+                .arg(format!("{} {{display_name}} :) ", label.ansi()));
+        }
+
         // Additional options
         for option in &config.options {
             cmd.arg(option);
         }
 
-        Ok(cmd)
+        cmd
+    }
+}
+
+impl DatabaseConnector for ClickHouseConnector {
+    fn build_command(&self, config: &DatabaseConfig) -> Result<Command> {
+        let user_config = user_client_config();
+        let root = user_config
+            .as_deref()
+            .map_or_else(|| "clickhouse".to_string(), config_root);
+        let config_file = match client_config_fragment(config, &root) {
+            Some(fragment) => {
+                let dir = get_state_dir()?.join(CLIENT_CONFIG_DIR_NAME);
+                Some(write_client_config(
+                    &dir,
+                    &config.alias,
+                    user_config.as_deref(),
+                    &fragment,
+                )?)
+            }
+            None => None,
+        };
+
+        Ok(self.command_for(config, uses_standalone_client(), config_file.as_deref()))
     }
 
     fn cli_tool_name(&self) -> &str {
         "clickhouse"
     }
+
+    /// Either the multi-call binary or the standalone client will do
+    fn check_availability(&self) -> Result<()> {
+        if which::which("clickhouse").is_ok() || which::which(STANDALONE_CLIENT).is_ok() {
+            Ok(())
+        } else {
+            Err(DbJumpError::CliToolNotFound(format!(
+                "clickhouse (or {})",
+                STANDALONE_CLIENT
+            )))
+        }
+    }
+
+    fn read_only_settings(&self) -> &'static [&'static str] {
+        &["readonly"]
+    }
+
+    fn default_port(&self, config: &DatabaseConfig) -> u16 {
+        // clickhouse client switches to the secure native port with --secure
+        if is_secure(config) {
+            9440
+        } else {
+            9000
+        }
+    }
+
+    fn supports_socket(&self) -> bool {
+        false
+    }
+
+    fn unsupported_tls_fields(&self) -> &'static [&'static str] {
+        &["server_name"]
+    }
+
+    fn field_flags(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("-h", "host"),
+            ("--host", "host"),
+            ("--port", "port"),
+            ("-u", "user"),
+            ("--user", "user"),
+            ("--password", "password"),
+            ("-d", "database"),
+            ("--database", "database"),
+            ("-s", "secure"),
+            ("--secure", "secure"),
+        ]
+    }
 }
 
 #[cfg(test)]
@@ -53,32 +371,212 @@ mod tests {
 
     fn create_test_config() -> DatabaseConfig {
         DatabaseConfig {
-            alias: "test".to_string(),
-            engine: DatabaseEngine::ClickHouse,
             host: Some("localhost".to_string()),
             port: Some(9000),
             user: Some("default".to_string()),
             password: Some("secret".to_string()),
             database: Some("mydb".to_string()),
             options: vec!["--multiline".to_string()],
+            ..DatabaseConfig::new("test", DatabaseEngine::ClickHouse)
         }
     }
 
+    /// Args for the multi-call binary, without a generated config
+    fn args(config: &DatabaseConfig) -> Vec<String> {
+        ClickHouseConnector
+            .command_for(config, false, None)
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
     #[test]
     fn test_build_command() {
-        let connector = ClickHouseConnector;
-        let config = create_test_config();
-
-        let cmd = connector.build_command(&config).unwrap();
-        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
-        assert!(args.contains(&std::ffi::OsStr::new("client")));
-        assert!(args.contains(&std::ffi::OsStr::new("-h")));
-        assert!(args.contains(&std::ffi::OsStr::new("localhost")));
-        assert!(args.contains(&std::ffi::OsStr::new("--database")));
-        assert!(args.contains(&std::ffi::OsStr::new("mydb")));
-        assert!(args.contains(&std::ffi::OsStr::new("--multiline")));
+        let args = args(&create_test_config());
+        assert_eq!(args[0], "client");
+        assert!(args.contains(&"-h".to_string()));
+        assert!(args.contains(&"localhost".to_string()));
+        assert!(args.contains(&"--database".to_string()));
+        assert!(args.contains(&"mydb".to_string()));
+        assert!(args.contains(&"--multiline".to_string()));
         // Password should not be in args (passed via env var)
-        assert!(!args.contains(&std::ffi::OsStr::new("--password")));
-        assert!(!args.contains(&std::ffi::OsStr::new("secret")));
+        assert!(!args.contains(&"--password".to_string()));
+        assert!(!args.contains(&"secret".to_string()));
+        assert!(!args.contains(&"--readonly=1".to_string()));
+        assert!(!args.contains(&"--config-file".to_string()));
+    }
+
+    #[test]
+    fn test_build_command_standalone() {
+        let mut config = create_test_config();
+        config.host = None;
+        config.hosts = vec!["ch1:9001".to_string(), "ch2".to_string()];
+
+        let cmd = ClickHouseConnector.command_for(&config, true, Some(Path::new("/tmp/ch.xml")));
+        assert_eq!(cmd.get_program(), "clickhouse-client");
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            &args[..10],
+            [
+                "--config-file",
+                "/tmp/ch.xml",
+                "--host",
+                "ch1",
+                "--port",
+                "9001",
+                "--host",
+                "ch2",
+                "--port",
+                "9000"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_command_environment_prompt() {
+        let mut config = create_test_config();
+        config.environment = Some("dev".to_string());
+
+        assert!(args(&config).contains(&"[DEV] {display_name} :) ".to_string()));
+    }
+
+    #[test]
+    fn test_build_command_read_only() {
+        let mut config = create_test_config();
+        config.read_only = true;
+
+        assert!(args(&config).contains(&"--readonly=1".to_string()));
+
+        // An alias's own readonly setting cannot undo it
+        config.settings = [("readonly".to_string(), toml::Value::Integer(0))]
+            .into_iter()
+            .collect();
+        let args = args(&config);
+        assert!(!args.contains(&"--readonly=0".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some("--multiline"));
+        assert!(args.contains(&"--readonly=1".to_string()));
+        assert!(client_config_fragment(&config, "clickhouse").is_none());
+    }
+
+    #[test]
+    fn test_build_command_multiple_hosts() {
+        let mut config = create_test_config();
+        config.host = None;
+        config.hosts = vec!["ch1:9001".to_string(), "ch2".to_string()];
+
+        assert_eq!(
+            &args(&config)[1..9],
+            ["--host", "ch1", "--port", "9001", "--host", "ch2", "--port", "9000"]
+        );
+    }
+
+    #[test]
+    fn test_build_command_tls() {
+        let mut config = create_test_config();
+        config.tls = Some(TlsConfig::default());
+
+        assert!(args(&config).contains(&"--secure".to_string()));
+        assert!(client_config_fragment(&config, "clickhouse").is_none());
+
+        config.tls = Some(TlsConfig {
+            mode: Some(TlsMode::Disable),
+            ..Default::default()
+        });
+        assert!(!args(&config).contains(&"--secure".to_string()));
+    }
+
+    #[test]
+    fn test_build_command_secure_and_settings() {
+        let connector = ClickHouseConnector;
+        let mut config = create_test_config();
+        config.port = None;
+        config.secure = true;
+        config.settings = [
+            ("max_threads".to_string(), toml::Value::Integer(8)),
+            ("send_logs_level".to_string(), toml::Value::from("trace")),
+        ]
+        .into_iter()
+        .collect();
+
+        let args = args(&config);
+        assert!(args.contains(&"--secure".to_string()));
+        assert!(args.contains(&"--max_threads=8".to_string()));
+        assert!(args.contains(&"--send_logs_level=trace".to_string()));
+        assert_eq!(connector.default_port(&config), 9440);
+        assert_eq!(
+            client_config_fragment(&config, "config").unwrap(),
+            "<config>\n    <max_threads>8</max_threads>\n    <send_logs_level>trace</send_logs_level>\n</config>\n"
+        );
+    }
+
+    #[test]
+    fn test_client_command() {
+        let cmd = client_command(true);
+        assert_eq!(cmd.get_program(), "clickhouse-client");
+        assert_eq!(cmd.get_args().count(), 0);
+
+        let cmd = client_command(false);
+        assert_eq!(cmd.get_program(), "clickhouse");
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["client"]);
+    }
+
+    #[test]
+    fn test_tls_client_config() {
+        let tls = TlsConfig {
+            mode: Some(TlsMode::VerifyCa),
+            ca_file: Some("/certs/a&b.pem".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            tls_client_config(&tls).unwrap(),
+            r#"    <openSSL>
+        <client>
+            <verificationMode>relaxed</verificationMode>
+            <caConfig>/certs/a&amp;b.pem</caConfig>
+        </client>
+    </openSSL>
+"#
+        );
+        assert!(tls_client_config(&TlsConfig::default()).is_none());
+    }
+
+    #[test]
+    fn test_xml_root() {
+        assert_eq!(
+            xml_root("<?xml version=\"1.0\"?>\n<!-- <old> -->\n<config>\n</config>"),
+            Some("config")
+        );
+        assert_eq!(xml_root("<clickhouse/>"), Some("clickhouse"));
+        assert_eq!(xml_root("no xml"), None);
+    }
+
+    #[test]
+    fn test_write_client_config_merges_with_user_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_config = dir.path().join("config.xml");
+        fs::write(&user_config, "<config><user>me</user></config>").unwrap();
+        let state = dir.path().join("state");
+
+        let path =
+            write_client_config(&state, "ch", Some(&user_config), "<config>\n</config>\n").unwrap();
+        assert_eq!(path, state.join("ch.xml"));
+        assert_eq!(config_root(&path), "config");
+        assert_eq!(
+            fs::read_to_string(state.join("ch.d").join("dbjump.xml")).unwrap(),
+            "<config>\n</config>\n"
+        );
+
+        // Rewritten without a user config on the next connection
+        let path =
+            write_client_config(&state, "ch", None, "<clickhouse>\n</clickhouse>\n").unwrap();
+        assert!(!fs::symlink_metadata(&path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(config_root(&path), "clickhouse");
+        assert_eq!(
+            fs::read_to_string(&user_config).unwrap(),
+            "<config><user>me</user></config>"
+        );
     }
 }
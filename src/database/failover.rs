@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::config::{DatabaseConfig, HostAddress, HostStrategy};
+use crate::database::types::DatabaseConnector;
+use crate::error::{DbJumpError, Result};
+
+/// How long to wait for each endpoint to accept a TCP connection
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Narrow `hosts` down to the single endpoint to connect to, unless the
+/// client is meant to fail over by itself
+pub fn choose_host<'a>(
+    config: &'a DatabaseConfig,
+    connector: &dyn DatabaseConnector,
+) -> Result<Cow<'a, DatabaseConfig>> {
+    let addresses = config.host_addresses();
+    if addresses.is_empty() || config.host_strategy() == HostStrategy::EngineNative {
+        return Ok(Cow::Borrowed(config));
+    }
+
+    let default_port = config
+        .port
+        .unwrap_or_else(|| connector.default_port(config));
+    let chosen = match config.host_strategy() {
+        HostStrategy::Random => {
+            let index = random_index(addresses.len());
+            addresses[index].clone()
+        }
+        _ => first_reachable(&addresses, default_port).ok_or_else(|| {
+            DbJumpError::NoReachableHost(config.alias.clone(), config.hosts.join(", "))
+        })?,
+    };
+
+    if addresses.len() > 1 {
+        eprintln!("Using {} for '{}'", chosen, config.alias);
+    }
+
+    Ok(Cow::Owned(with_host(config, chosen)))
+}
+
+/// `config` narrowed down to the single endpoint `address`
+pub fn with_host(config: &DatabaseConfig, address: HostAddress) -> DatabaseConfig {
+    DatabaseConfig {
+        host: Some(address.host),
+        port: address.port.or(config.port),
+        hosts: Vec::new(),
+        ..config.clone()
+    }
+}
+
+fn first_reachable(addresses: &[HostAddress], default_port: u16) -> Option<HostAddress> {
+    addresses.iter().find_map(|address| {
+        let port = address.port.unwrap_or(default_port);
+        if is_reachable(&address.host, port) {
+            Some(address.clone())
+        } else {
+            eprintln!("Warning: {}:{} is not reachable", address.uri_host(), port);
+            None
+        }
+    })
+}
+
+fn is_reachable(host: &str, port: u16) -> bool {
+    let Ok(addrs) = (host, port).to_socket_addrs() else {
+        return false;
+    };
+    addrs
+        .into_iter()
+        .any(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok())
+}
+
+/// A random index below `len`, seeded from the standard library's
+/// per-process random hasher keys
+fn random_index(len: usize) -> usize {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(crate::utils::unix_now());
+    (hasher.finish() % len as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseEngine;
+    use crate::database::PostgreSQLConnector;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_first_reachable_skips_closed_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap().port();
+        // Bind and drop to find a port that is very likely closed
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let config = DatabaseConfig {
+            hosts: vec![
+                format!("127.0.0.1:{}", closed),
+                format!("127.0.0.1:{}", open),
+            ],
+            port: Some(5432),
+            ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+        };
+        let chosen = choose_host(&config, &PostgreSQLConnector).unwrap();
+        assert_eq!(chosen.host.as_deref(), Some("127.0.0.1"));
+        assert_eq!(chosen.port, Some(open));
+        assert!(chosen.hosts.is_empty());
+
+        let config = DatabaseConfig {
+            hosts: vec![format!("127.0.0.1:{}", closed)],
+            ..config
+        };
+        assert!(matches!(
+            choose_host(&config, &PostgreSQLConnector),
+            Err(DbJumpError::NoReachableHost(..))
+        ));
+    }
+
+    #[test]
+    fn test_engine_native_keeps_every_host() {
+        let config = DatabaseConfig {
+            hosts: vec!["pg1".to_string(), "pg2".to_string()],
+            host_strategy: Some(HostStrategy::EngineNative),
+            ..DatabaseConfig::new("pg", DatabaseEngine::PostgreSQL)
+        };
+        let chosen = choose_host(&config, &PostgreSQLConnector).unwrap();
+        assert!(matches!(chosen, Cow::Borrowed(_)));
+
+        let config = DatabaseConfig {
+            host_strategy: Some(HostStrategy::Random),
+            ..config
+        };
+        let chosen = choose_host(&config, &PostgreSQLConnector).unwrap();
+        assert!(matches!(chosen.host.as_deref(), Some("pg1" | "pg2")));
+    }
+}
@@ -1,12 +1,17 @@
 pub mod clickhouse;
 pub mod executor;
+pub mod failover;
 pub mod mongodb;
 pub mod mysql;
 pub mod postgresql;
+pub mod prompt;
+#[cfg(unix)]
+pub mod pty;
+pub mod recorder;
 pub mod types;
 
 pub use clickhouse::ClickHouseConnector;
-pub use executor::execute_connection;
+pub use executor::{execute_connection, preview_connection};
 pub use mongodb::MongoDBConnector;
 pub use mysql::MySQLConnector;
 pub use postgresql::PostgreSQLConnector;
@@ -1,30 +1,141 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::config::DatabaseConfig;
+use crate::config::{DatabaseConfig, TlsMode};
+use crate::database::prompt::PromptLabel;
 use crate::database::types::DatabaseConnector;
 use crate::error::Result;
 
+const DEFAULTS_FILE_FLAG: &str = "--defaults-extra-file=";
+const READ_ONLY_INIT_COMMAND: &str = "SET SESSION TRANSACTION READ ONLY";
+
+/// Distinguishes option files written by one process
+static OPTION_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 pub struct MySQLConnector;
 
-impl DatabaseConnector for MySQLConnector {
-    fn build_command(&self, config: &DatabaseConfig) -> Result<Command> {
+/// `[client]` options for the credentials and TLS settings
+fn client_options(config: &DatabaseConfig) -> Vec<(&'static str, String)> {
+    let mut options = Vec::new();
+    if let Some(ref user) = config.user {
+        options.push(("user", option_value(user)));
+    }
+    if let Some(ref password) = config.password {
+        options.push(("password", option_value(password)));
+    }
+    if let Some(ref tls) = config.tls {
+        let mode = match tls.mode() {
+            TlsMode::Disable => "DISABLED",
+            TlsMode::Require => "REQUIRED",
+            TlsMode::VerifyCa => "VERIFY_CA",
+            TlsMode::VerifyFull => "VERIFY_IDENTITY",
+        };
+        options.push(("ssl-mode", mode.to_string()));
+        let files = [
+            ("ssl-ca", &tls.ca_file),
+            ("ssl-cert", &tls.cert_file),
+            ("ssl-key", &tls.key_file),
+        ];
+        for (name, path) in files {
+            if let Some(path) = path {
+                options.push((name, option_value(&path.to_string_lossy())));
+            }
+        }
+    }
+    options
+}
+
+/// Option file content for `options`, or None when there are none
+fn option_file(options: &[(&str, String)]) -> Option<String> {
+    if options.is_empty() {
+        return None;
+    }
+
+    let mut content = String::from("[client]\n");
+    for (name, value) in options {
+        content.push_str(&format!("{}={}\n", name, value));
+    }
+    Some(content)
+}
+
+/// Quote a value for a MySQL option file, escaping what the parser unescapes
+fn option_value(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Write `content` to a new file in the temp directory that only the
+/// current user can read
+fn write_option_file(content: &str) -> Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!(
+        "dbjump-mysql-{}-{}.cnf",
+        std::process::id(),
+        OPTION_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    file.write_all(content.as_bytes())?;
+
+    Ok(path)
+}
+
+/// Statements for `--init-command`, with the read-only one last so the
+/// configured command can't undo it
+fn init_command(config: &DatabaseConfig) -> Option<String> {
+    let read_only = config.read_only.then_some(READ_ONLY_INIT_COMMAND);
+    match (config.init_command.as_deref(), read_only) {
+        (Some(command), Some(read_only)) => Some(format!("{}; {}", command, read_only)),
+        (command, read_only) => command.or(read_only).map(str::to_string),
+    }
+}
+
+impl MySQLConnector {
+    /// The command for `config`, reading credentials from `defaults_file`
+    fn command_for(&self, config: &DatabaseConfig, defaults_file: Option<&str>) -> Command {
         let mut cmd = Command::new(self.cli_tool_name());
 
-        // Password via env var to avoid process list exposure
-        if let Some(ref password) = config.password {
-            cmd.env("MYSQL_PWD", password);
+        // mysql only accepts --defaults-extra-file as the first argument
+        if let Some(defaults_file) = defaults_file {
+            cmd.arg(format!("{}{}", DEFAULTS_FILE_FLAG, defaults_file));
         }
 
         if let Some(ref host) = config.host {
             cmd.arg("-h").arg(host);
         }
 
+        if let Some(ref socket) = config.socket {
+            cmd.arg(format!("--socket={}", socket));
+        }
+
         if let Some(port) = config.port {
             cmd.arg("-P").arg(port.to_string());
         }
 
-        if let Some(ref user) = config.user {
-            cmd.arg("-u").arg(user);
+        if let Some(ref charset) = config.charset {
+            cmd.arg(format!("--default-character-set={}", charset));
+        }
+
+        if let Some(label) = PromptLabel::from_config(config) {
+            cmd.arg(format!("--prompt={} \\d> ", label.ansi()));
+        }
+
+        if let Some(command) = init_command(config) {
+            cmd.arg(format!("--init-command={}", command));
         }
 
         // Additional options
@@ -37,46 +148,212 @@ impl DatabaseConnector for MySQLConnector {
             cmd.arg(database);
         }
 
-        Ok(cmd)
+        cmd
+    }
+}
+
+impl DatabaseConnector for MySQLConnector {
+    fn build_command(&self, config: &DatabaseConfig) -> Result<Command> {
+        // Credentials and TLS settings go in an option file rather than
+        // MYSQL_PWD, which other users can read on some systems
+        let defaults_file = match option_file(&client_options(config)) {
+            Some(content) => Some(write_option_file(&content)?.display().to_string()),
+            None => None,
+        };
+        Ok(self.command_for(config, defaults_file.as_deref()))
+    }
+
+    /// Names the options instead of writing the file
+    fn preview_command(&self, config: &DatabaseConfig) -> Result<Command> {
+        let options = client_options(config);
+        let names: Vec<_> = options.iter().map(|(name, _)| *name).collect();
+        let placeholder = format!("<generated: {}>", names.join(", "));
+        Ok(self.command_for(
+            config,
+            (!options.is_empty()).then_some(placeholder.as_str()),
+        ))
     }
 
     fn cli_tool_name(&self) -> &str {
         "mysql"
     }
+
+    fn temporary_files(&self, cmd: &Command) -> Vec<PathBuf> {
+        cmd.get_args()
+            .next()
+            .and_then(|arg| arg.to_str()?.strip_prefix(DEFAULTS_FILE_FLAG))
+            .map(PathBuf::from)
+            .into_iter()
+            .collect()
+    }
+
+    fn default_port(&self, _config: &DatabaseConfig) -> u16 {
+        3306
+    }
+
+    fn supports_multiple_hosts(&self) -> bool {
+        false
+    }
+
+    fn unsupported_tls_fields(&self) -> &'static [&'static str] {
+        &["server_name"]
+    }
+
+    fn field_flags(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("-h", "host"),
+            ("--host", "host"),
+            ("-P", "port"),
+            ("--port", "port"),
+            ("-S", "socket"),
+            ("--socket", "socket"),
+            ("-u", "user"),
+            ("--user", "user"),
+            ("-p", "password"),
+            ("--password", "password"),
+            ("-D", "database"),
+            ("--database", "database"),
+            ("--ssl-mode", "tls.mode"),
+            ("--ssl-ca", "tls.ca_file"),
+            ("--ssl-cert", "tls.cert_file"),
+            ("--ssl-key", "tls.key_file"),
+            ("--default-character-set", "charset"),
+            ("--init-command", "init_command"),
+        ]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::DatabaseEngine;
+    use crate::config::{DatabaseEngine, TlsConfig};
 
     fn create_test_config() -> DatabaseConfig {
         DatabaseConfig {
-            alias: "test".to_string(),
-            engine: DatabaseEngine::MySQL,
             host: Some("localhost".to_string()),
             port: Some(3306),
             user: Some("root".to_string()),
             password: Some("secret".to_string()),
             database: Some("mydb".to_string()),
-            options: vec![],
+            ..DatabaseConfig::new("test", DatabaseEngine::MySQL)
         }
     }
 
+    /// Build the command and remove the option file it wrote, returning
+    /// the args and the file's content
+    fn build(config: &DatabaseConfig) -> (Vec<String>, Option<String>) {
+        let connector = MySQLConnector;
+        let cmd = connector.build_command(config).unwrap();
+        let args = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let content = connector.temporary_files(&cmd).first().map(|path| {
+            let content = std::fs::read_to_string(path).unwrap();
+            std::fs::remove_file(path).unwrap();
+            content
+        });
+        (args, content)
+    }
+
     #[test]
     fn test_build_command() {
-        let connector = MySQLConnector;
         let config = create_test_config();
 
-        let cmd = connector.build_command(&config).unwrap();
-        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
-        assert!(args.contains(&std::ffi::OsStr::new("-h")));
-        assert!(args.contains(&std::ffi::OsStr::new("localhost")));
-        assert!(args.contains(&std::ffi::OsStr::new("-u")));
-        assert!(args.contains(&std::ffi::OsStr::new("root")));
+        let (args, content) = build(&config);
+        assert!(args[0].starts_with("--defaults-extra-file="));
+        assert!(args.contains(&"-h".to_string()));
+        assert!(args.contains(&"localhost".to_string()));
         // Database should be the last positional arg
-        assert_eq!(args.last(), Some(&std::ffi::OsStr::new("mydb")));
-        // Password should not be in args (passed via MYSQL_PWD env var)
-        assert!(!args.contains(&std::ffi::OsStr::new("secret")));
+        assert_eq!(args.last().map(String::as_str), Some("mydb"));
+        // Credentials should not be in args or env (written to the option file)
+        assert!(!args.iter().any(|arg| arg == "root" || arg == "secret"));
+        assert_eq!(
+            content.as_deref(),
+            Some("[client]\nuser=\"root\"\npassword=\"secret\"\n")
+        );
+
+        let config = DatabaseConfig {
+            user: None,
+            password: None,
+            ..config
+        };
+        let (args, content) = build(&config);
+        assert_eq!(args[0], "-h");
+        assert!(content.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_option_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = write_option_file("[client]\n").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_option_value() {
+        assert_eq!(option_value("p#ss\"word"), "\"p#ss\\\"word\"");
+        assert_eq!(option_value("ab\"#cd"), "\"ab\\\"#cd\"");
+        assert_eq!(option_value("a\\b\nc"), "\"a\\\\b\\nc\"");
+    }
+
+    #[test]
+    fn test_build_command_environment_prompt() {
+        let mut config = create_test_config();
+        config.environment = Some("dev".to_string());
+
+        let (args, _) = build(&config);
+        assert!(args.contains(&"--prompt=[DEV] \\d> ".to_string()));
+    }
+
+    #[test]
+    fn test_build_command_read_only() {
+        let mut config = create_test_config();
+        config.read_only = true;
+
+        let (args, _) = build(&config);
+        assert!(args.contains(&"--init-command=SET SESSION TRANSACTION READ ONLY".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some("mydb"));
+
+        config.init_command = Some("SET time_zone = '+00:00'".to_string());
+        config.charset = Some("utf8mb4".to_string());
+        let (args, _) = build(&config);
+        assert!(args.contains(
+            &"--init-command=SET time_zone = '+00:00'; SET SESSION TRANSACTION READ ONLY"
+                .to_string()
+        ));
+        assert!(args.contains(&"--default-character-set=utf8mb4".to_string()));
+    }
+
+    #[test]
+    fn test_build_command_socket() {
+        let mut config = create_test_config();
+        config.host = None;
+        config.port = None;
+        config.socket = Some("/var/run/mysqld/mysqld.sock".to_string());
+
+        let (args, _) = build(&config);
+        assert!(args.contains(&"--socket=/var/run/mysqld/mysqld.sock".to_string()));
+        assert!(!args.contains(&"-h".to_string()));
+    }
+
+    #[test]
+    fn test_build_command_tls() {
+        let mut config = create_test_config();
+        config.tls = Some(TlsConfig {
+            ca_file: Some("/certs/ca.pem".into()),
+            ..Default::default()
+        });
+
+        let (args, content) = build(&config);
+        assert!(!args.iter().any(|arg| arg.starts_with("--ssl")));
+        let content = content.unwrap();
+        assert!(content.contains("ssl-mode=VERIFY_IDENTITY\n"));
+        assert!(content.contains("ssl-ca=\"/certs/ca.pem\"\n"));
+        assert!(!content.contains("ssl-key"));
     }
 }
@@ -1,11 +1,138 @@
 use std::process::Command;
 
-use crate::config::DatabaseConfig;
+use crate::config::{DatabaseConfig, DatabaseEngine};
+use crate::database::prompt::PromptLabel;
 use crate::database::types::DatabaseConnector;
 use crate::error::Result;
+use crate::utils::scalar_to_string;
 
 pub struct PostgreSQLConnector;
 
+/// Server settings to apply when the session starts, in PGOPTIONS order
+fn session_settings(config: &DatabaseConfig) -> Vec<(String, String)> {
+    let mut settings = Vec::new();
+    let typed = [
+        ("search_path", &config.search_path),
+        ("role", &config.role),
+        ("statement_timeout", &config.statement_timeout),
+    ];
+    for (name, value) in typed {
+        if let Some(value) = value {
+            settings.push((name.to_string(), value.clone()));
+        }
+    }
+
+    for (name, value) in &config.settings {
+        settings.push((name.clone(), scalar_to_string(value)));
+    }
+
+    // Last, so that nothing before it can switch read-only off again
+    if config.read_only {
+        settings.push((
+            "default_transaction_read_only".to_string(),
+            "on".to_string(),
+        ));
+    }
+    settings
+}
+
+/// `-c name=value` switches for PGOPTIONS, or None when there are none.
+/// libpq splits PGOPTIONS on spaces, so spaces and backslashes in values
+/// are escaped with a backslash.
+fn pgoptions(config: &DatabaseConfig) -> Option<String> {
+    let settings = session_settings(config);
+    if settings.is_empty() {
+        return None;
+    }
+
+    let escape = |text: &str| text.replace('\\', "\\\\").replace(' ', "\\ ");
+    let switches: Vec<_> = settings
+        .iter()
+        .map(|(name, value)| format!("-c {}={}", escape(name), escape(value)))
+        .collect();
+    Some(switches.join(" "))
+}
+
+/// A pg_service.conf with a section per PostgreSQL alias, so other libpq
+/// tools can connect with `service=<alias>`. Passwords are left out; keep
+/// them in ~/.pgpass.
+pub fn service_file(databases: &[DatabaseConfig]) -> String {
+    let mut out = String::from("# Generated by dbjump pg-service\n");
+
+    for db in databases
+        .iter()
+        .filter(|db| db.engine == DatabaseEngine::PostgreSQL)
+    {
+        // A service entry cannot refer to another service
+        if db.service.is_some() {
+            continue;
+        }
+
+        let mut params: Vec<(&str, String)> = Vec::new();
+        let addresses = db.host_addresses();
+        if !addresses.is_empty() {
+            let hosts: Vec<_> = addresses.iter().map(|a| a.host.as_str()).collect();
+            params.push(("host", hosts.join(",")));
+            let ports: Vec<_> = addresses
+                .iter()
+                .map(|a| {
+                    a.port
+                        .or(db.port)
+                        .map(|p| p.to_string())
+                        .unwrap_or_default()
+                })
+                .collect();
+            if ports.iter().any(|port| !port.is_empty()) {
+                params.push(("port", ports.join(",")));
+            }
+            params.push(("target_session_attrs", "read-write".to_string()));
+        } else {
+            if let Some(host) = db.socket.as_ref().or(db.host.as_ref()) {
+                params.push(("host", host.clone()));
+            }
+            if let Some(port) = db.port {
+                params.push(("port", port.to_string()));
+            }
+        }
+
+        let strings = [
+            ("user", &db.user),
+            ("dbname", &db.database),
+            ("application_name", &db.application_name),
+        ];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                params.push((key, value.clone()));
+            }
+        }
+
+        if let Some(ref tls) = db.tls {
+            params.push(("sslmode", tls.mode().to_string()));
+            let files = [
+                ("sslrootcert", &tls.ca_file),
+                ("sslcert", &tls.cert_file),
+                ("sslkey", &tls.key_file),
+            ];
+            for (key, path) in files {
+                if let Some(path) = path {
+                    params.push((key, path.display().to_string()));
+                }
+            }
+        }
+
+        if let Some(options) = pgoptions(db) {
+            params.push(("options", options));
+        }
+
+        out.push_str(&format!("\n[{}]\n", db.alias));
+        for (key, value) in params {
+            out.push_str(&format!("{}={}\n", key, value));
+        }
+    }
+
+    out
+}
+
 impl DatabaseConnector for PostgreSQLConnector {
     fn build_command(&self, config: &DatabaseConfig) -> Result<Command> {
         let mut cmd = Command::new(self.cli_tool_name());
@@ -15,11 +142,56 @@ impl DatabaseConnector for PostgreSQLConnector {
             cmd.env("PGPASSWORD", password);
         }
 
-        if let Some(ref host) = config.host {
-            cmd.arg("-h").arg(host);
+        // Fields set on the alias override the ones from the service entry
+        if let Some(ref service) = config.service {
+            cmd.env("PGSERVICE", service);
+        }
+
+        if let Some(ref application_name) = config.application_name {
+            cmd.env("PGAPPNAME", application_name);
+        }
+
+        let server_name = config.tls.as_ref().and_then(|tls| tls.server_name.as_ref());
+        match (&config.host, server_name) {
+            // libpq verifies the certificate against the host name and
+            // connects to the numeric address in PGHOSTADDR instead
+            (Some(host), Some(server_name)) => {
+                cmd.arg("-h").arg(server_name);
+                cmd.env("PGHOSTADDR", host);
+            }
+            (Some(host), None) => {
+                cmd.arg("-h").arg(host);
+            }
+            _ => {}
         }
 
-        if let Some(port) = config.port {
+        // psql takes the socket directory as the host; the port still picks
+        // the socket file within it
+        if let Some(ref socket) = config.socket {
+            cmd.arg("-h").arg(socket);
+        }
+
+        // libpq tries each host in turn until it finds the primary
+        let addresses = config.host_addresses();
+        if !addresses.is_empty() {
+            let hosts: Vec<_> = addresses.iter().map(|a| a.host.as_str()).collect();
+            cmd.arg("-h").arg(hosts.join(","));
+            cmd.env("PGTARGETSESSIONATTRS", "read-write");
+        }
+
+        if addresses.iter().any(|a| a.port.is_some()) {
+            // An empty entry falls back to the default port for that host
+            let ports: Vec<_> = addresses
+                .iter()
+                .map(|a| {
+                    a.port
+                        .or(config.port)
+                        .map(|p| p.to_string())
+                        .unwrap_or_default()
+                })
+                .collect();
+            cmd.arg("-p").arg(ports.join(","));
+        } else if let Some(port) = config.port {
             cmd.arg("-p").arg(port.to_string());
         }
 
@@ -31,6 +203,30 @@ impl DatabaseConnector for PostgreSQLConnector {
             cmd.arg("-d").arg(database);
         }
 
+        if let Some(ref tls) = config.tls {
+            cmd.env("PGSSLMODE", tls.mode().to_string());
+            let files = [
+                ("PGSSLROOTCERT", &tls.ca_file),
+                ("PGSSLCERT", &tls.cert_file),
+                ("PGSSLKEY", &tls.key_file),
+            ];
+            for (var, path) in files {
+                if let Some(path) = path {
+                    cmd.env(var, path);
+                }
+            }
+        }
+
+        if let Some(label) = PromptLabel::from_config(config) {
+            cmd.arg(format!("--set=PROMPT1={} %/%R%x%# ", label.psql()));
+            cmd.arg(format!("--set=PROMPT2={} %/%R%x%# ", label.psql()));
+        }
+
+        // Session settings passed to the server at connection time
+        if let Some(options) = pgoptions(config) {
+            cmd.env("PGOPTIONS", options);
+        }
+
         // Additional options
         for option in &config.options {
             cmd.arg(option);
@@ -42,23 +238,42 @@ impl DatabaseConnector for PostgreSQLConnector {
     fn cli_tool_name(&self) -> &str {
         "psql"
     }
+
+    fn default_port(&self, _config: &DatabaseConfig) -> u16 {
+        5432
+    }
+
+    fn read_only_settings(&self) -> &'static [&'static str] {
+        &["default_transaction_read_only", "transaction_read_only"]
+    }
+
+    fn field_flags(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("-h", "host"),
+            ("--host", "host"),
+            ("-p", "port"),
+            ("--port", "port"),
+            ("-U", "user"),
+            ("--username", "user"),
+            ("-d", "database"),
+            ("--dbname", "database"),
+        ]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::DatabaseEngine;
+    use crate::config::{DatabaseEngine, TlsConfig, TlsMode};
 
     fn create_test_config() -> DatabaseConfig {
         DatabaseConfig {
-            alias: "test".to_string(),
-            engine: DatabaseEngine::PostgreSQL,
             host: Some("localhost".to_string()),
             port: Some(5432),
             user: Some("postgres".to_string()),
             password: Some("secret".to_string()),
             database: Some("mydb".to_string()),
-            options: vec![],
+            ..DatabaseConfig::new("test", DatabaseEngine::PostgreSQL)
         }
     }
 
@@ -78,4 +293,171 @@ mod tests {
         // Password should not be in args (passed via PGPASSWORD env var)
         assert!(!args.contains(&std::ffi::OsStr::new("secret")));
     }
+
+    #[test]
+    fn test_build_command_environment_prompt() {
+        let connector = PostgreSQLConnector;
+        let mut config = create_test_config();
+        config.environment = Some("staging".to_string());
+
+        let cmd = connector.build_command(&config).unwrap();
+        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
+        assert!(args.contains(&std::ffi::OsStr::new("--set=PROMPT1=[STAGING] %/%R%x%# ")));
+    }
+
+    #[test]
+    fn test_build_command_read_only() {
+        let connector = PostgreSQLConnector;
+        let mut config = create_test_config();
+        config.read_only = true;
+
+        let cmd = connector.build_command(&config).unwrap();
+        let pgoptions = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "PGOPTIONS")
+            .and_then(|(_, value)| value);
+        assert_eq!(
+            pgoptions,
+            Some(std::ffi::OsStr::new("-c default_transaction_read_only=on"))
+        );
+    }
+
+    #[test]
+    fn test_build_command_socket() {
+        let connector = PostgreSQLConnector;
+        let mut config = create_test_config();
+        config.host = None;
+        config.socket = Some("/var/run/postgresql".to_string());
+
+        let cmd = connector.build_command(&config).unwrap();
+        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
+        assert_eq!(&args[..4], ["-h", "/var/run/postgresql", "-p", "5432"]);
+    }
+
+    #[test]
+    fn test_build_command_multiple_hosts() {
+        let connector = PostgreSQLConnector;
+        let mut config = create_test_config();
+        config.host = None;
+        config.port = None;
+        config.hosts = vec!["pg1:5433".to_string(), "pg2".to_string()];
+
+        let cmd = connector.build_command(&config).unwrap();
+        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
+        assert_eq!(&args[..4], ["-h", "pg1,pg2", "-p", "5433,"]);
+        let attrs = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "PGTARGETSESSIONATTRS")
+            .and_then(|(_, value)| value);
+        assert_eq!(attrs, Some(std::ffi::OsStr::new("read-write")));
+    }
+
+    #[test]
+    fn test_build_command_tls() {
+        let connector = PostgreSQLConnector;
+        let mut config = create_test_config();
+        config.host = Some("10.0.0.5".to_string());
+        config.tls = Some(TlsConfig {
+            mode: Some(TlsMode::VerifyCa),
+            ca_file: Some("/certs/ca.pem".into()),
+            server_name: Some("db.internal".to_string()),
+            ..Default::default()
+        });
+
+        let cmd = connector.build_command(&config).unwrap();
+        let env = |name: &str| {
+            cmd.get_envs()
+                .find(|(key, _)| *key == name)
+                .and_then(|(_, value)| value)
+                .map(|value| value.to_string_lossy().to_string())
+        };
+        assert_eq!(env("PGSSLMODE").as_deref(), Some("verify-ca"));
+        assert_eq!(env("PGSSLROOTCERT").as_deref(), Some("/certs/ca.pem"));
+        assert_eq!(env("PGSSLCERT"), None);
+        assert_eq!(env("PGHOSTADDR").as_deref(), Some("10.0.0.5"));
+
+        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
+        assert_eq!(&args[..2], ["-h", "db.internal"]);
+    }
+
+    #[test]
+    fn test_build_command_session_settings() {
+        let connector = PostgreSQLConnector;
+        let mut config = create_test_config();
+        config.read_only = true;
+        config.service = Some("prod".to_string());
+        config.application_name = Some("dbjump".to_string());
+        config.search_path = Some("app, public".to_string());
+        config.statement_timeout = Some("30s".to_string());
+        config.settings = [("work_mem".to_string(), toml::Value::from("64MB"))]
+            .into_iter()
+            .collect();
+
+        let cmd = connector.build_command(&config).unwrap();
+        let env = |name: &str| {
+            cmd.get_envs()
+                .find(|(key, _)| *key == name)
+                .and_then(|(_, value)| value)
+                .map(|value| value.to_string_lossy().to_string())
+        };
+        assert_eq!(env("PGSERVICE").as_deref(), Some("prod"));
+        assert_eq!(env("PGAPPNAME").as_deref(), Some("dbjump"));
+        assert_eq!(
+            env("PGOPTIONS").as_deref(),
+            Some(
+                "-c search_path=app,\\ public -c statement_timeout=30s -c work_mem=64MB \
+                 -c default_transaction_read_only=on"
+            )
+        );
+    }
+
+    #[test]
+    fn test_read_only_overrides_settings() {
+        let connector = PostgreSQLConnector;
+        let mut config = create_test_config();
+        config.read_only = true;
+        config.settings = [(
+            "default_transaction_read_only".to_string(),
+            toml::Value::from("off"),
+        )]
+        .into_iter()
+        .collect();
+
+        let cmd = connector.build_command(&config).unwrap();
+        let pgoptions = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "PGOPTIONS")
+            .and_then(|(_, value)| value);
+        assert_eq!(
+            pgoptions,
+            Some(std::ffi::OsStr::new(
+                "-c default_transaction_read_only=off -c default_transaction_read_only=on"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_service_file() {
+        let databases = [
+            DatabaseConfig {
+                role: Some("readonly".to_string()),
+                tls: Some(TlsConfig {
+                    mode: Some(TlsMode::Require),
+                    ..Default::default()
+                }),
+                ..create_test_config()
+            },
+            DatabaseConfig {
+                service: Some("test".to_string()),
+                ..DatabaseConfig::new("via-service", DatabaseEngine::PostgreSQL)
+            },
+            DatabaseConfig::new("mongo", DatabaseEngine::MongoDB),
+        ];
+
+        assert_eq!(
+            service_file(&databases),
+            "# Generated by dbjump pg-service\n\n[test]\nhost=localhost\nport=5432\n\
+             user=postgres\ndbname=mydb\nsslmode=require\noptions=-c role=readonly\n"
+        );
+    }
 }
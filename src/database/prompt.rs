@@ -0,0 +1,55 @@
+use crate::config::{DatabaseConfig, PromptColor};
+
+/// Environment tag shown in the client prompt, e.g. "[PROD]"
+pub struct PromptLabel {
+    pub text: String,
+    pub color: Option<PromptColor>,
+}
+
+impl PromptLabel {
+    /// Returns None for aliases without an environment
+    pub fn from_config(config: &DatabaseConfig) -> Option<Self> {
+        config.environment.as_ref().map(|env| PromptLabel {
+            text: format!("[{}]", env.to_uppercase()),
+            color: config.prompt_color,
+        })
+    }
+
+    /// Label wrapped in raw ANSI escape sequences
+    pub fn ansi(&self) -> String {
+        match self.color {
+            Some(color) => format!("\x1b[1;{}m{}\x1b[0m", color.ansi_code(), self.text),
+            None => self.text.clone(),
+        }
+    }
+
+    /// Label in psql prompt syntax, with escapes marked as non-printing
+    pub fn psql(&self) -> String {
+        match self.color {
+            Some(color) => format!("%[%033[1;{}m%]{}%[%033[0m%]", color.ansi_code(), self.text),
+            None => self.text.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseEngine;
+
+    #[test]
+    fn test_prompt_label() {
+        let mut config = DatabaseConfig::new("test", DatabaseEngine::PostgreSQL);
+        assert!(PromptLabel::from_config(&config).is_none());
+
+        config.environment = Some("prod".to_string());
+        config.prompt_color = Some(PromptColor::Red);
+        let label = PromptLabel::from_config(&config).unwrap();
+        assert_eq!(label.ansi(), "\x1b[1;31m[PROD]\x1b[0m");
+        assert_eq!(label.psql(), "%[%033[1;31m%][PROD]%[%033[0m%]");
+
+        config.prompt_color = None;
+        let label = PromptLabel::from_config(&config).unwrap();
+        assert_eq!(label.ansi(), "[PROD]");
+    }
+}
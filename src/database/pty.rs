@@ -0,0 +1,152 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::error::Result;
+
+/// Puts our terminal in raw mode and restores it when dropped
+pub struct RawModeGuard {
+    original: libc::termios,
+}
+
+impl RawModeGuard {
+    pub fn enable() -> Option<Self> {
+        // SAFETY: termios is plain data and is only used after tcgetattr
+        // has filled it in.
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return None;
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+            Some(RawModeGuard { original })
+        }
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        // SAFETY: restores the attributes saved in enable()
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+pub fn window_size() -> Option<libc::winsize> {
+    // SAFETY: TIOCGWINSZ fills in the winsize struct we pass
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 {
+            Some(size)
+        } else {
+            None
+        }
+    }
+}
+
+/// Open a pseudo-terminal, returning (master, slave)
+pub fn open_pty(size: Option<&libc::winsize>) -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut master = -1;
+    let mut slave = -1;
+    let size_ptr = size.map_or(std::ptr::null(), |s| s as *const libc::winsize);
+
+    // SAFETY: openpty writes two new file descriptors, which we take
+    // ownership of immediately.
+    unsafe {
+        if libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            size_ptr as _,
+            size_ptr as _,
+        ) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)))
+    }
+}
+
+/// Queue `input` on the pty as if it had been typed, without echoing it
+pub fn type_ahead(master: &OwnedFd, slave: &OwnedFd, input: &str) -> io::Result<()> {
+    // SAFETY: termios is plain data and is only used after tcgetattr has
+    // filled it in
+    unsafe {
+        let mut attributes: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(slave.as_raw_fd(), &mut attributes) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        attributes.c_lflag &= !(libc::ECHO | libc::ECHONL);
+        if libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &attributes) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    File::from(master.try_clone()?).write_all(format!("{}\n", input).as_bytes())
+}
+
+/// Keystrokes forwarded from our terminal to a client reading a pty
+pub struct InputRelay {
+    master: OwnedFd,
+}
+
+impl InputRelay {
+    /// Give `cmd` a pty for stdin with `input` already typed at it, so a
+    /// client that prompts for a password finds the answer waiting. Its
+    /// output still goes straight to our terminal.
+    pub fn attach(cmd: &mut Command, input: &str) -> Result<Self> {
+        let (master, slave) = open_pty(window_size().as_ref())?;
+        type_ahead(&master, &slave, input)?;
+        cmd.stdin(Stdio::from(slave));
+        Ok(InputRelay { master })
+    }
+
+    /// Start forwarding once the client is running. Our terminal stays in
+    /// raw mode until the guard is dropped, so keys like Ctrl-C reach the
+    /// client as input.
+    pub fn start(self) -> Option<RawModeGuard> {
+        let mut master = File::from(self.master);
+        let raw_mode = RawModeGuard::enable();
+
+        // This thread stays blocked on stdin after the client exits and
+        // ends with the process
+        thread::spawn(move || {
+            let mut stdin = io::stdin();
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stdin.read(&mut buf) {
+                if n == 0 || master.write_all(&buf[..n]).is_err() {
+                    break;
+                }
+            }
+        });
+
+        raw_mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_relay_types_ahead() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("read line; printf '%s' \"$line\"");
+        cmd.stdout(Stdio::piped());
+
+        let relay = InputRelay::attach(&mut cmd, "s3cret").unwrap();
+        let child = cmd.spawn().unwrap();
+        drop(cmd);
+
+        let output = child.wait_with_output().unwrap();
+        drop(relay);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "s3cret");
+    }
+}
@@ -0,0 +1,381 @@
+use serde_json::json;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::path::get_state_dir;
+use crate::error::{DbJumpError, Result};
+use crate::utils::{format_timestamp, set_permissions_700, unix_now};
+
+const SESSIONS_DIR_NAME: &str = "sessions";
+const SESSION_EXTENSION: &str = "cast";
+
+/// Pauses longer than this are shortened during replay
+const MAX_REPLAY_IDLE: Duration = Duration::from_secs(2);
+
+pub fn get_sessions_dir() -> Result<PathBuf> {
+    Ok(get_state_dir()?.join(SESSIONS_DIR_NAME))
+}
+
+/// Create the transcript path for a new session of `alias`
+pub fn new_session_path(alias: &str) -> Result<PathBuf> {
+    let dir = get_sessions_dir()?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+        set_permissions_700(&dir)?;
+    }
+
+    let stamp = format_timestamp(unix_now()).replace(':', "-");
+    Ok(dir.join(format!("{}-{}.{}", stamp, alias, SESSION_EXTENSION)))
+}
+
+/// Recorded sessions, oldest first
+pub fn list_sessions() -> Result<Vec<PathBuf>> {
+    let dir = get_sessions_dir()?;
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == SESSION_EXTENSION) {
+            sessions.push(path);
+        }
+    }
+    sessions.sort();
+
+    Ok(sessions)
+}
+
+/// Resolve a session given as a path, a file name in the sessions
+/// directory, or a file name without the extension
+pub fn find_session(session: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(session);
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    let dir = get_sessions_dir()?;
+    [
+        dir.join(session),
+        dir.join(format!("{}.{}", session, SESSION_EXTENSION)),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+    .ok_or_else(|| DbJumpError::ConfigNotFound(format!("session '{}'", session)))
+}
+
+/// Transcript writer in asciicast v2 format: a JSON header line followed
+/// by one `[seconds, "o" | "i", data]` line per chunk of output or input
+struct CastWriter {
+    file: File,
+    start: Instant,
+}
+
+impl CastWriter {
+    fn create(path: &Path, title: &str, size: Option<(u16, u16)>) -> Result<Self> {
+        // Typed input may include secrets, so the file is never readable by
+        // others, not even briefly
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(path)?;
+
+        let (width, height) = size.unwrap_or((80, 24));
+        let header = json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": unix_now(),
+            "title": title,
+        });
+
+        let mut writer = CastWriter {
+            file,
+            start: Instant::now(),
+        };
+        writeln!(writer.file, "{}", header)?;
+        Ok(writer)
+    }
+
+    fn event(&mut self, kind: &str, data: &str) -> io::Result<()> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        writeln!(self.file, "{}", json!([elapsed, kind, data]))
+    }
+}
+
+/// Buffers a trailing partial UTF-8 sequence so that chunk boundaries never
+/// split a character in the transcript
+#[derive(Default)]
+struct Utf8Carry {
+    pending: Vec<u8>,
+}
+
+impl Utf8Carry {
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let valid_up_to = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+
+        let rest = self.pending.split_off(valid_up_to);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+}
+
+/// Run `cmd` under a pseudo-terminal, mirroring it to our terminal while
+/// writing input and output to a transcript. `input` is typed at the client
+/// first and left out of the transcript. Returns the client's exit code.
+#[cfg(unix)]
+pub fn record_session(cmd: Command, path: &Path, title: &str, input: Option<&str>) -> Result<i32> {
+    unix::record_session(cmd, path, title, input)
+}
+
+#[cfg(not(unix))]
+pub fn record_session(
+    _cmd: Command,
+    _path: &Path,
+    _title: &str,
+    _input: Option<&str>,
+) -> Result<i32> {
+    Err(DbJumpError::ExecutionError(
+        "Session recording is only supported on Unix".to_string(),
+    ))
+}
+
+/// Play back a transcript's output with its original timing
+pub fn replay_session(path: &Path, speed: f64) -> Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut stdout = io::stdout();
+    let mut previous = 0.0;
+
+    // The first line is the header
+    for line in reader.lines().skip(1) {
+        let line = line?;
+        let Some((time, kind, data)) = parse_event(&line) else {
+            continue;
+        };
+        if kind != "o" {
+            continue;
+        }
+
+        let delay = Duration::from_secs_f64(((time - previous) / speed).max(0.0));
+        thread::sleep(delay.min(MAX_REPLAY_IDLE));
+        previous = time;
+
+        stdout.write_all(data.as_bytes())?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+fn parse_event(line: &str) -> Option<(f64, String, String)> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let event = value.as_array()?;
+    Some((
+        event.first()?.as_f64()?,
+        event.get(1)?.as_str()?.to_string(),
+        event.get(2)?.as_str()?.to_string(),
+    ))
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use crate::database::executor::DeferredSignals;
+    use crate::database::pty::{open_pty, type_ahead, window_size, RawModeGuard};
+    use std::os::fd::AsRawFd;
+    use std::os::unix::process::CommandExt;
+    use std::process::Stdio;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static WINDOW_RESIZED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn on_window_resize(_signal: libc::c_int) {
+        WINDOW_RESIZED.store(true, Ordering::Relaxed);
+    }
+
+    pub fn record_session(
+        mut cmd: Command,
+        path: &Path,
+        title: &str,
+        input: Option<&str>,
+    ) -> Result<i32> {
+        let size = window_size();
+        let (master, slave) = open_pty(size.as_ref())?;
+        // Typed ahead before the relay starts, so it never reaches the
+        // transcript
+        if let Some(input) = input {
+            type_ahead(&master, &slave, input)?;
+        }
+
+        cmd.stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+
+        // SAFETY: only async-signal-safe calls between fork and exec. The
+        // client gets its own session with the pty as controlling terminal.
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                if libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        // Before spawning, so a failure leaves no client running on a pty
+        // nobody reads
+        let writer = Arc::new(Mutex::new(CastWriter::create(
+            path,
+            title,
+            size.map(|s| (s.ws_col, s.ws_row)),
+        )?));
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| DbJumpError::ExecutionError(e.to_string()))?;
+        DeferredSignals::forward_to(child.id());
+        // Drop our copies of the slave side so reads see EOF when the client exits
+        drop(cmd);
+
+        let raw_mode = RawModeGuard::enable();
+
+        // SAFETY: installs a handler that only stores to an atomic
+        unsafe {
+            libc::signal(
+                libc::SIGWINCH,
+                on_window_resize as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        }
+
+        // Forward keystrokes to the client. This thread stays blocked on
+        // stdin after the client exits and ends with the process.
+        let mut master_in = File::from(master.try_clone()?);
+        let input_writer = Arc::clone(&writer);
+        thread::spawn(move || {
+            let mut stdin = io::stdin();
+            let mut carry = Utf8Carry::default();
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stdin.read(&mut buf) {
+                if n == 0 || master_in.write_all(&buf[..n]).is_err() {
+                    break;
+                }
+                if let Ok(mut writer) = input_writer.lock() {
+                    let _ = writer.event("i", &carry.push(&buf[..n]));
+                }
+            }
+        });
+
+        // Mirror the client's output until the pty closes
+        let master_fd = master.as_raw_fd();
+        let mut master_out = File::from(master);
+        let mut stdout = io::stdout();
+        let mut carry = Utf8Carry::default();
+        let mut buf = [0u8; 8192];
+        loop {
+            if WINDOW_RESIZED.swap(false, Ordering::Relaxed) {
+                if let Some(size) = window_size() {
+                    // SAFETY: TIOCSWINSZ reads the winsize struct we pass
+                    unsafe {
+                        libc::ioctl(master_fd, libc::TIOCSWINSZ, &size);
+                    }
+                }
+            }
+
+            let n = match master_out.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                // Linux reports EIO once the slave side has been closed
+                Err(_) => break,
+            };
+            stdout.write_all(&buf[..n])?;
+            stdout.flush()?;
+            if let Ok(mut writer) = writer.lock() {
+                writer.event("o", &carry.push(&buf[..n]))?;
+            }
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| DbJumpError::ExecutionError(e.to_string()))?;
+        drop(raw_mode);
+
+        Ok(status.code().unwrap_or(-1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_carry_keeps_split_characters() {
+        let bytes = "héllo".as_bytes();
+        let mut carry = Utf8Carry::default();
+        let first = carry.push(&bytes[..2]);
+        let second = carry.push(&bytes[2..]);
+        assert_eq!(first, "h");
+        assert_eq!(second, "éllo");
+    }
+
+    #[test]
+    fn test_parse_event() {
+        assert_eq!(
+            parse_event(r#"[0.5, "o", "hi\r\n"]"#),
+            Some((0.5, "o".to_string(), "hi\r\n".to_string()))
+        );
+        assert_eq!(parse_event(r#"{"version": 2}"#), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_record_session_captures_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("printf recorded-output");
+
+        let code = record_session(cmd, &path, "test", None).unwrap();
+        assert_eq!(code, 0);
+
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let transcript = fs::read_to_string(&path).unwrap();
+        let mut lines = transcript.lines();
+        assert!(lines.next().unwrap().contains("\"version\":2"));
+        let output: String = lines
+            .filter_map(parse_event)
+            .filter(|(_, kind, _)| kind == "o")
+            .map(|(_, _, data)| data)
+            .collect();
+        assert_eq!(output, "recorded-output");
+
+        // The transcript already exists, so nothing is started
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("touch started");
+        cmd.current_dir(dir.path());
+        assert!(record_session(cmd, &path, "test", None).is_err());
+        assert!(!dir.path().join("started").exists());
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::process::Command;
 
 use crate::config::DatabaseConfig;
@@ -7,9 +8,18 @@ pub trait DatabaseConnector {
     /// Build the command to execute
     fn build_command(&self, config: &DatabaseConfig) -> Result<Command>;
 
+    /// The command `build_command` would produce, for display only. Files
+    /// that would hold secrets are not written.
+    fn preview_command(&self, config: &DatabaseConfig) -> Result<Command> {
+        self.build_command(config)
+    }
+
     /// Get the name of the CLI tool
     fn cli_tool_name(&self) -> &str;
 
+    /// Port the client connects to when none is configured
+    fn default_port(&self, config: &DatabaseConfig) -> u16;
+
     /// Check if the CLI tool is available in PATH
     fn check_availability(&self) -> Result<()> {
         which::which(self.cli_tool_name())
@@ -17,6 +27,44 @@ pub trait DatabaseConnector {
         Ok(())
     }
 
+    /// Client flags that set the same thing as a typed field, as (flag, field)
+    fn field_flags(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Entries of `settings` that could switch a read-only session back to
+    /// read-write
+    fn read_only_settings(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether the client can connect over a Unix domain socket
+    fn supports_socket(&self) -> bool {
+        true
+    }
+
+    /// Whether the client can fail over between several hosts by itself
+    fn supports_multiple_hosts(&self) -> bool {
+        true
+    }
+
+    /// Files `build_command` wrote for `cmd` that must be removed once the
+    /// client exits
+    fn temporary_files(&self, _cmd: &Command) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Password to type at the client's own prompt, for clients that can
+    /// take it neither from the environment nor from a file
+    fn password_input(&self, _config: &DatabaseConfig) -> Option<String> {
+        None
+    }
+
+    /// Fields of the `tls` table this client has no way to honor
+    fn unsupported_tls_fields(&self) -> &'static [&'static str] {
+        &[]
+    }
+
     /// Format a preview string for display (e.g., in fzf)
     fn format_preview(&self, config: &DatabaseConfig) -> String {
         config.format_info(true)
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::config::Diagnostic;
+
 #[derive(Error, Debug)]
 pub enum DbJumpError {
     #[error("Configuration file not found at {0}")]
@@ -8,11 +10,20 @@ pub enum DbJumpError {
     #[error("Failed to parse configuration: {0}")]
     ConfigParseError(String),
 
-    #[error("Database alias '{0}' not found")]
-    AliasNotFound(String),
+    #[error("Invalid configuration\n\n{}", render_diagnostics(.0))]
+    InvalidConfig(Vec<Diagnostic>),
+
+    #[error("Database alias '{0}' not found{}", did_you_mean(.1))]
+    AliasNotFound(String, Vec<String>),
+
+    #[error("Duplicate alias '{0}' found in {1} and {2}")]
+    DuplicateAlias(String, String, String),
 
-    #[error("Duplicate alias '{0}' found in configuration")]
-    DuplicateAlias(String),
+    #[error("'{0}' extends unknown template or alias '{1}'")]
+    UnknownParent(String, String),
+
+    #[error("Inheritance cycle detected: {0}")]
+    InheritanceCycle(String),
 
     #[error("Invalid alias '{0}': must contain only letters, numbers, hyphens, and underscores")]
     InvalidAliasFormat(String),
@@ -29,6 +40,15 @@ pub enum DbJumpError {
     #[error("Failed to execute command: {0}")]
     ExecutionError(String),
 
+    #[error("Connecting to '{0}' requires confirmation. Use --yes or set DBJUMP_ASSUME_YES when not running in a terminal.")]
+    ConfirmationRequired(String),
+
+    #[error("None of the hosts for '{0}' are reachable: {1}")]
+    NoReachableHost(String, String),
+
+    #[error("Connection to '{0}' aborted")]
+    ConnectionAborted(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -36,4 +56,20 @@ pub enum DbJumpError {
     ConfigError(String),
 }
 
+fn render_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(Diagnostic::render)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(". Did you mean: {}?", suggestions.join(", "))
+    }
+}
+
 pub type Result<T> = std::result::Result<T, DbJumpError>;
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::path::get_data_dir;
+use crate::error::{DbJumpError, Result};
+use crate::utils::{set_permissions_600, set_permissions_700};
+
+const HISTORY_FILE_NAME: &str = "history.json";
+
+/// Once the ranks add up to more than this, they are all scaled down so
+/// that aliases which are no longer used fade out
+const MAX_TOTAL_RANK: f64 = 1000.0;
+const AGING_FACTOR: f64 = 0.9;
+
+const HOUR: u64 = 3600;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Connection history used to rank aliases by frecency, as zoxide does
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct History {
+    #[serde(default)]
+    entries: BTreeMap<String, HistoryEntry>,
+    /// Most recently connected alias
+    #[serde(default)]
+    last: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HistoryEntry {
+    pub rank: f64,
+    pub last_access: u64,
+}
+
+impl History {
+    pub fn load() -> Result<Self> {
+        Self::load_from(&get_history_path()?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| DbJumpError::ConfigParseError(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(DbJumpError::IoError(e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&get_history_path()?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.exists() {
+                fs::create_dir_all(dir)?;
+                set_permissions_700(dir)?;
+            }
+        }
+
+        let content =
+            serde_json::to_string(self).map_err(|e| DbJumpError::ConfigError(e.to_string()))?;
+        fs::write(path, content)?;
+        set_permissions_600(path)?;
+
+        Ok(())
+    }
+
+    pub fn record(&mut self, alias: &str, now: u64) {
+        let entry = self
+            .entries
+            .entry(alias.to_string())
+            .or_insert(HistoryEntry {
+                rank: 0.0,
+                last_access: now,
+            });
+        entry.rank += 1.0;
+        entry.last_access = now;
+        self.last = Some(alias.to_string());
+
+        let total: f64 = self.entries.values().map(|e| e.rank).sum();
+        if total > MAX_TOTAL_RANK {
+            for entry in self.entries.values_mut() {
+                entry.rank *= AGING_FACTOR;
+            }
+            self.entries.retain(|_, entry| entry.rank >= 1.0);
+        }
+    }
+
+    pub fn last(&self) -> Option<&str> {
+        self.last.as_deref()
+    }
+
+    /// Rank weighted by how recently the alias was used; 0 if never used
+    pub fn score(&self, alias: &str, now: u64) -> f64 {
+        let Some(entry) = self.entries.get(alias) else {
+            return 0.0;
+        };
+
+        let age = now.saturating_sub(entry.last_access);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+
+        entry.rank * weight
+    }
+}
+
+fn get_history_path() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join(HISTORY_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_use_outranks_old_frequent_use() {
+        let now = 10 * WEEK;
+        let mut history = History::default();
+        for _ in 0..5 {
+            history.record("old-favorite", now - 3 * WEEK);
+        }
+        history.record("today", now - 60);
+        history.record("today", now - 30);
+
+        assert!(history.score("today", now) > history.score("old-favorite", now));
+        assert_eq!(history.score("never-used", now), 0.0);
+        assert_eq!(history.last(), Some("today"));
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join(HISTORY_FILE_NAME);
+
+        let mut history = History::default();
+        history.record("prod", 100);
+        history.save_to(&path).unwrap();
+
+        let loaded = History::load_from(&path).unwrap();
+        assert_eq!(loaded.last(), Some("prod"));
+        assert_eq!(loaded.score("prod", 100), 4.0);
+    }
+
+    #[test]
+    fn test_aging_drops_stale_entries() {
+        let mut history = History::default();
+        history.record("stale", 0);
+        for i in 0..(MAX_TOTAL_RANK as u64) {
+            history.record("busy", i);
+        }
+
+        assert_eq!(history.score("stale", 0), 0.0);
+        assert!(history.score("busy", MAX_TOTAL_RANK as u64) > 0.0);
+    }
+}
@@ -1,7 +1,9 @@
+pub mod audit;
 pub mod cli;
 pub mod config;
 pub mod database;
 pub mod error;
+pub mod history;
 pub mod utils;
 
 pub use config::{Config, DatabaseConfig, DatabaseEngine};
@@ -1,9 +1,24 @@
 use clap::Parser;
-use dbjump::cli::{generate_completions, generate_shell_init, Cli, Commands};
-use dbjump::config::{get_config_path, validate_config, Config};
-use dbjump::database::{execute_connection, get_connector};
+use dbjump::audit;
+use dbjump::cli::args::{ListFormat, ListSort, ValidateFormat};
+use dbjump::cli::picker::Picker;
+use dbjump::cli::{generate_completions, generate_shell_init, prompt, Cli, Commands};
+use dbjump::config::path::PROJECT_CONFIG_NAME;
+use dbjump::config::writer::{database_table, write_config_file, write_private_file};
+use dbjump::config::{
+    check_config, find_project_config, get_config_path, validate_config, Config, ConfigFile,
+    DatabaseConfig, TrustStore,
+};
+use dbjump::database::postgresql::service_file;
+use dbjump::database::{execute_connection, get_connector, preview_connection, recorder};
 use dbjump::error::{DbJumpError, Result};
-use std::process;
+use dbjump::history::History;
+use dbjump::utils::{format_timestamp, parse_since, unix_now};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
 
 fn main() {
     if let Err(e) = run() {
@@ -16,12 +31,55 @@ fn run() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Some(Commands::Connect { alias, extra_args }) => {
-            let config = load_config()?;
-            let db_config = config.find_by_alias(&alias)?;
-            let connector = get_connector(&db_config.engine);
-            execute_connection(db_config, connector.as_ref(), &extra_args)?;
-            Ok(())
+        Some(Commands::Connect {
+            alias,
+            yes,
+            read_only,
+            record,
+            dry_run,
+            extra_args,
+        }) => {
+            let config = load_connect_config()?;
+            let mut db_config = match alias {
+                Some(alias) => {
+                    let db_config = config.find_by_alias(&alias)?;
+                    if db_config.alias != alias {
+                        eprintln!("Matched '{}' to '{}'", alias, db_config.alias);
+                    }
+                    db_config.clone()
+                }
+                None => {
+                    if !prompt::is_interactive() {
+                        return Err(DbJumpError::MissingField("alias".to_string()));
+                    }
+                    let mut databases: Vec<&DatabaseConfig> = config.database.iter().collect();
+                    sort_frecent(&mut databases)?;
+                    match Picker::new(databases).run()? {
+                        Some(db_config) => db_config.clone(),
+                        None => return Ok(()),
+                    }
+                }
+            };
+            if read_only {
+                db_config.read_only = true;
+            }
+            if record {
+                db_config.record = true;
+            }
+            if dry_run {
+                return show_command(&db_config, &extra_args);
+            }
+            connect(&db_config, yes, &extra_args)
+        }
+
+        Some(Commands::Last { yes, extra_args }) => {
+            let config = load_connect_config()?;
+            let history = History::load()?;
+            let alias = history.last().ok_or_else(|| {
+                DbJumpError::ConfigError("No previous connection recorded".to_string())
+            })?;
+            let db_config = config.find_by_alias(alias)?;
+            connect(db_config, yes, &extra_args)
         }
 
         Some(Commands::Init { force }) => {
@@ -32,34 +90,258 @@ fn run() -> Result<()> {
             Ok(())
         }
 
-        Some(Commands::List { format }) => {
+        Some(Commands::List {
+            format,
+            tag,
+            engine,
+            environment,
+            sort,
+        }) => {
+            let config = load_config()?;
+            let mut databases: Vec<&DatabaseConfig> = config
+                .database
+                .iter()
+                .filter(|db| tag.iter().all(|t| db.has_tag(t)))
+                .filter(|db| engine.as_ref().is_none_or(|e| &db.engine == e))
+                .filter(|db| {
+                    environment.is_none() || db.environment.as_deref() == environment.as_deref()
+                })
+                .collect();
+
+            match sort {
+                ListSort::Config => {}
+                ListSort::Alias => databases.sort_by(|a, b| a.alias.cmp(&b.alias)),
+                ListSort::Environment => sort_by_environment(&mut databases),
+                ListSort::Frecent => sort_frecent(&mut databases)?,
+                ListSort::EnvironmentFrecent => {
+                    // Stable, so frecency order holds within each group
+                    sort_frecent(&mut databases)?;
+                    sort_by_environment(&mut databases);
+                }
+            }
+
+            print_list(&databases, format)
+        }
+
+        Some(Commands::Info { alias }) => {
+            let config = load_config()?;
+            let db_config = config.find_by_alias(&alias)?;
+            println!("{}", db_config.format_info(true));
+            Ok(())
+        }
+
+        Some(Commands::ShowCommand { alias, extra_args }) => {
             let config = load_config()?;
+            let db_config = config.find_by_alias(&alias)?;
+            show_command(db_config, &extra_args)
+        }
+
+        Some(Commands::Validate { format, strict }) => {
+            let diagnostics = match load_config() {
+                Ok(config) => check_config(&config),
+                Err(DbJumpError::InvalidConfig(diagnostics)) => diagnostics,
+                Err(e) => return Err(e),
+            };
+
             match format {
-                dbjump::cli::args::ListFormat::Text => {
-                    for db in &config.database {
-                        println!("{}", db.alias);
+                ValidateFormat::Text => {
+                    for diagnostic in &diagnostics {
+                        eprintln!("{}\n", diagnostic.render());
                     }
                 }
-                dbjump::cli::args::ListFormat::Json => {
-                    let json = serde_json::to_string_pretty(&config.database)
+                ValidateFormat::Json => {
+                    let json = serde_json::to_string_pretty(&diagnostics)
                         .map_err(|e| DbJumpError::ConfigError(e.to_string()))?;
                     println!("{}", json);
                 }
             }
+
+            let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+            let warnings = diagnostics.len() - errors;
+            if errors > 0 || (strict && warnings > 0) {
+                return Err(DbJumpError::ConfigError(format!(
+                    "found {} error(s) and {} warning(s)",
+                    errors, warnings
+                )));
+            }
+            if matches!(format, ValidateFormat::Text) {
+                if warnings > 0 {
+                    println!("Configuration is valid, with {} warning(s)", warnings);
+                } else {
+                    println!("Configuration is valid!");
+                }
+            }
             Ok(())
         }
 
-        Some(Commands::Info { alias }) => {
-            let config = load_config()?;
-            let db_config = config.find_by_alias(&alias)?;
-            println!("{}", db_config.format_info(true));
+        Some(Commands::Add {
+            alias,
+            engine,
+            host,
+            socket,
+            port,
+            user,
+            ask_password,
+            database,
+            tags,
+            environment,
+            extends,
+            options,
+            file,
+        }) => {
+            let password = if ask_password {
+                Some(prompt::read_password("Password: ")?)
+            } else {
+                None
+            };
+            let db_config = DatabaseConfig {
+                host,
+                socket,
+                port,
+                user,
+                password,
+                database,
+                tags,
+                environment,
+                extends,
+                options,
+                ..DatabaseConfig::new(&alias, engine)
+            };
+
+            let path = match file {
+                Some(path) => path,
+                None => get_config_path()?,
+            };
+            let mut config_file = ConfigFile::open(&path)?;
+            config_file.add_database(database_table(&db_config))?;
+            save_config_file(&config_file)?;
+            println!("Added '{}' to {}", alias, path.display());
+            Ok(())
+        }
+
+        Some(Commands::Edit { alias }) => {
+            let path = match alias {
+                Some(alias) => defining_file(&alias)?,
+                None => get_config_path()?,
+            };
+            edit_config_file(&path)
+        }
+
+        Some(Commands::Remove { alias }) => {
+            let mut config_file = ConfigFile::open(&defining_file(&alias)?)?;
+            config_file.remove_database(&alias)?;
+            save_config_file(&config_file)?;
+            println!("Removed '{}' from {}", alias, config_file.path().display());
+            Ok(())
+        }
+
+        Some(Commands::Rename { alias, new_alias }) => {
+            let mut config_file = ConfigFile::open(&defining_file(&alias)?)?;
+            config_file.rename_database(&alias, &new_alias)?;
+            save_config_file(&config_file)?;
+            println!(
+                "Renamed '{}' to '{}' in {}",
+                alias,
+                new_alias,
+                config_file.path().display()
+            );
             Ok(())
         }
 
-        Some(Commands::Validate) => {
+        Some(Commands::Copy { alias, new_alias }) => {
+            let mut config_file = ConfigFile::open(&defining_file(&alias)?)?;
+            config_file.copy_database(&alias, &new_alias)?;
+            save_config_file(&config_file)?;
+            println!(
+                "Copied '{}' to '{}' in {}",
+                alias,
+                new_alias,
+                config_file.path().display()
+            );
+            Ok(())
+        }
+
+        Some(Commands::Log { alias, since }) => {
+            let since = since
+                .map(|since| {
+                    parse_since(&since, unix_now())
+                        .map(format_timestamp)
+                        .ok_or_else(|| {
+                            DbJumpError::ConfigError(format!("Invalid --since value '{}'", since))
+                        })
+                })
+                .transpose()?;
+            let records = audit::read_records(
+                &audit::get_audit_log_path()?,
+                alias.as_deref(),
+                since.as_deref(),
+            )?;
+            for record in records {
+                println!(
+                    "{}  {}  {:?}  host={}  user={}  tty={}  {}",
+                    record.timestamp,
+                    record.alias,
+                    record.engine,
+                    record.host.as_deref().unwrap_or("-"),
+                    record.os_user.as_deref().unwrap_or("-"),
+                    record.tty.as_deref().unwrap_or("-"),
+                    record.args.join(" ")
+                );
+            }
+            Ok(())
+        }
+
+        Some(Commands::Replay { session, speed }) => {
+            match session {
+                Some(session) => {
+                    recorder::replay_session(&recorder::find_session(&session)?, speed)?
+                }
+                None => {
+                    for path in recorder::list_sessions()? {
+                        if let Some(name) = path.file_name() {
+                            println!("{}", name.to_string_lossy());
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        Some(Commands::PgService { output }) => {
             let config = load_config()?;
-            validate_config(&config)?;
-            println!("Configuration is valid!");
+            let content = service_file(&config.database);
+            match output {
+                Some(path) => {
+                    write_config_file(&path, &content)?;
+                    eprintln!(
+                        "Wrote {}; connect with PGSERVICEFILE={} psql service=<alias>",
+                        path.display(),
+                        path.display()
+                    );
+                }
+                None => print!("{}", content),
+            }
+            Ok(())
+        }
+
+        Some(Commands::Trust { path }) => {
+            let path = project_config_arg(path)?;
+            let mut store = TrustStore::load()?;
+            store.trust(&path)?;
+            store.save()?;
+            println!("Trusted {}", path.display());
+            Ok(())
+        }
+
+        Some(Commands::Untrust { path }) => {
+            let path = project_config_arg(path)?;
+            let mut store = TrustStore::load()?;
+            if store.revoke(&path)? {
+                store.save()?;
+                println!("Revoked trust for {}", path.display());
+            } else {
+                println!("{} was not trusted", path.display());
+            }
             Ok(())
         }
 
@@ -81,7 +363,129 @@ fn run() -> Result<()> {
     }
 }
 
+fn connect(db_config: &DatabaseConfig, yes: bool, extra_args: &[String]) -> Result<()> {
+    if db_config.requires_confirmation() && !yes {
+        prompt::confirm_connection(db_config)?;
+    }
+    let connector = get_connector(&db_config.engine);
+    execute_connection(db_config, connector.as_ref(), extra_args)
+}
+
+fn show_command(db_config: &DatabaseConfig, extra_args: &[String]) -> Result<()> {
+    let connector = get_connector(&db_config.engine);
+    println!(
+        "{}",
+        preview_connection(db_config, connector.as_ref(), extra_args)?
+    );
+    Ok(())
+}
+
+/// Group by environment, aliases without one last; ties keep their order
+fn sort_by_environment(databases: &mut [&DatabaseConfig]) {
+    databases.sort_by_key(|db| (db.environment.is_none(), db.environment.clone()));
+}
+
+/// Most frequently and recently used first; ties keep config order
+fn sort_frecent(databases: &mut [&DatabaseConfig]) -> Result<()> {
+    let history = History::load()?;
+    let now = unix_now();
+    databases.sort_by(|a, b| {
+        history
+            .score(&b.alias, now)
+            .total_cmp(&history.score(&a.alias, now))
+    });
+    Ok(())
+}
+
+/// `[project]` or `[<file name>]` for an alias that comes from somewhere
+/// other than the main config file, e.g. conf.d or an include
+fn source_marker(db: &DatabaseConfig, config_path: &Path) -> Option<String> {
+    let source = db.source.as_deref()?;
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if canonical(source) == canonical(config_path) {
+        return None;
+    }
+
+    let file_name = source.file_name()?.to_string_lossy();
+    if file_name == PROJECT_CONFIG_NAME {
+        Some("[project]".to_string())
+    } else {
+        Some(format!("[{}]", file_name))
+    }
+}
+
+fn print_list(databases: &[&DatabaseConfig], format: ListFormat) -> Result<()> {
+    match format {
+        ListFormat::Text => {
+            // Only people get the markers; scripts and the shell completion
+            // cache read bare aliases
+            let marked = io::stdout().is_terminal();
+            let config_path = get_config_path()?;
+            let alias_width = databases.iter().map(|db| db.alias.len()).max().unwrap_or(0);
+
+            for db in databases {
+                match source_marker(db, &config_path).filter(|_| marked) {
+                    Some(marker) => println!("{:<alias_width$}  {}", db.alias, marker),
+                    None => println!("{}", db.alias),
+                }
+            }
+        }
+        ListFormat::Long => {
+            let alias_width = databases.iter().map(|db| db.alias.len()).max().unwrap_or(0);
+            let env_width = databases
+                .iter()
+                .map(|db| db.environment.as_deref().unwrap_or("-").len())
+                .max()
+                .unwrap_or(0);
+            let tags_width = databases
+                .iter()
+                .map(|db| format_tags(db).len())
+                .max()
+                .unwrap_or(0);
+
+            for db in databases {
+                println!(
+                    "{:<alias_width$}  {:<10}  {:<env_width$}  {:<tags_width$}  {}",
+                    db.alias,
+                    format!("{:?}", db.engine),
+                    db.environment.as_deref().unwrap_or("-"),
+                    format_tags(db),
+                    db.source_display(),
+                );
+            }
+        }
+        ListFormat::Json => {
+            let json = serde_json::to_string_pretty(databases)
+                .map_err(|e| DbJumpError::ConfigError(e.to_string()))?;
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+fn format_tags(db: &DatabaseConfig) -> String {
+    if db.tags.is_empty() {
+        "-".to_string()
+    } else {
+        db.tags.join(",")
+    }
+}
+
 fn load_config() -> Result<Config> {
+    let (config_path, project_path) = config_paths(false)?;
+    Config::load(&config_path, project_path.as_deref())
+}
+
+/// Like `load_config`, but offer to trust a new or modified project file.
+/// Only for commands a user runs on purpose: the shell completion and the
+/// fzf widget call the others in the background.
+fn load_connect_config() -> Result<Config> {
+    let (config_path, project_path) = config_paths(true)?;
+    Config::load(&config_path, project_path.as_deref())
+}
+
+/// The global config file and the trusted project file, if any
+fn config_paths(ask_trust: bool) -> Result<(PathBuf, Option<PathBuf>)> {
     let config_path = get_config_path()?;
 
     if !config_path.exists() {
@@ -91,5 +495,181 @@ fn load_config() -> Result<Config> {
         )));
     }
 
-    Config::from_file(config_path)
+    let project_path = match find_project_config() {
+        Some(path) if is_project_config_trusted(&path, ask_trust)? => Some(path),
+        Some(path) => {
+            eprintln!(
+                "Warning: ignoring untrusted project config {}. Run 'dbjump trust' to allow it.",
+                path.display()
+            );
+            None
+        }
+        None => None,
+    };
+
+    Ok((config_path, project_path))
+}
+
+/// The file that defines `alias`. Editing commands never act on a fuzzy
+/// match.
+fn defining_file(alias: &str) -> Result<PathBuf> {
+    let config = load_config()?;
+    let db_config = config.find_by_alias(alias)?;
+    if db_config.alias != alias {
+        return Err(DbJumpError::AliasNotFound(
+            alias.to_string(),
+            vec![db_config.alias.clone()],
+        ));
+    }
+
+    db_config
+        .source
+        .clone()
+        .ok_or_else(|| DbJumpError::ConfigError(format!("no source file for '{}'", alias)))
+}
+
+/// Validate an edited file against the whole configuration, then write it
+fn save_config_file(file: &ConfigFile) -> Result<()> {
+    save_config_contents(file.path(), &file.contents())
+}
+
+fn save_config_contents(path: &Path, content: &str) -> Result<()> {
+    let (config_path, project_path) = config_paths(false)?;
+    let config = Config::load_with(&config_path, project_path.as_deref(), Some((path, content)))?;
+    validate_config(&config)?;
+    // A file outside the loaded configuration, e.g. from `add --file`, is
+    // checked on its own instead
+    if !is_loaded_from(&config, path) {
+        validate_config(&Config::load_with(path, None, Some((path, content)))?)?;
+    }
+    write_config_file(path, content)?;
+
+    // Edits made through dbjump shouldn't revoke trust in the project file,
+    // whether they touch it or one of its includes
+    if let Some(project_path) = project_path {
+        let mut store = TrustStore::load()?;
+        store.trust(&project_path)?;
+        store.save()?;
+    }
+
+    Ok(())
+}
+
+/// Whether any alias or template in `config` was read from `path`
+fn is_loaded_from(config: &Config, path: &Path) -> bool {
+    let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    config
+        .database
+        .iter()
+        .filter_map(|db| db.source.as_deref())
+        .chain(config.template.iter().filter_map(|t| t.source.as_deref()))
+        .any(|source| fs::canonicalize(source).is_ok_and(|source| source == key))
+}
+
+/// Edit a scratch copy of `path` and only replace the original once the
+/// result validates, like visudo
+fn edit_config_file(path: &Path) -> Result<()> {
+    if !prompt::is_interactive() {
+        return Err(DbJumpError::ConfigError(
+            "'dbjump edit' needs a terminal".to_string(),
+        ));
+    }
+
+    let original = fs::read_to_string(path)?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // Hidden, so conf.d doesn't pick it up; keeps .toml for editor highlighting
+    let edit_path = path.with_file_name(format!(".{}.edit.toml", file_name));
+    write_private_file(&edit_path, &original)?;
+
+    let result = edit_until_valid(path, &edit_path, &original);
+    let _ = fs::remove_file(&edit_path);
+    result
+}
+
+fn edit_until_valid(path: &Path, edit_path: &Path, original: &str) -> Result<()> {
+    loop {
+        run_editor(edit_path)?;
+        let content = fs::read_to_string(edit_path)?;
+        if content == original {
+            println!("No changes made to {}", path.display());
+            return Ok(());
+        }
+
+        match save_config_contents(path, &content) {
+            Ok(()) => {
+                println!("Saved {}", path.display());
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                let answer =
+                    prompt::read_line("What now? (e)dit again or (d)iscard changes [e]: ")?;
+                if matches!(answer.to_lowercase().as_str(), "d" | "discard") {
+                    println!("Discarded changes; {} was not modified", path.display());
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn run_editor(path: &Path) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+
+    // Go through the shell so the editor may carry arguments, e.g. "code --wait"
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(DbJumpError::ExecutionError(format!(
+            "editor '{}' exited with {}",
+            editor, status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check the allow-list. With `ask`, the user is asked about project files
+/// seen for the first time (or changed since they were trusted).
+fn is_project_config_trusted(project_path: &Path, ask: bool) -> Result<bool> {
+    let mut store = TrustStore::load()?;
+    if store.is_trusted(project_path)? {
+        return Ok(true);
+    }
+
+    if !ask || !prompt::is_interactive() {
+        return Ok(false);
+    }
+
+    let question = format!(
+        "Found new or modified project config {}. Trust it?",
+        project_path.display()
+    );
+    if !prompt::confirm(&question)? {
+        return Ok(false);
+    }
+
+    store.trust(project_path)?;
+    store.save()?;
+    Ok(true)
+}
+
+fn project_config_arg(path: Option<PathBuf>) -> Result<PathBuf> {
+    path.or_else(find_project_config).ok_or_else(|| {
+        DbJumpError::ConfigNotFound(format!(
+            "{} in the current directory or its parents",
+            dbjump::config::path::PROJECT_CONFIG_NAME
+        ))
+    })
 }
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Set file permissions to 600 (owner read/write only)
 #[cfg(unix)]
@@ -29,3 +29,183 @@ pub fn set_permissions_700(_path: &Path) -> std::io::Result<()> {
     // On non-Unix systems, do nothing
     Ok(())
 }
+
+/// Expand a leading `~` to the user's home directory
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if path == "~" {
+        if let Some(home) = dirs::home_dir() {
+            return home;
+        }
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// A scalar TOML value as a client expects it on the command line or in a
+/// URI: strings without quotes, everything else as written
+pub fn scalar_to_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Quote `word` for a POSIX shell, leaving it bare when that's safe
+pub fn shell_quote(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,@%+".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Current time as seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp,
+/// e.g. "2024-05-01T13:45:00Z"
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Parse a point in time given either relative to now ("30m", "12h", "7d",
+/// "2w") or as a UTC date ("2024-05-01") or timestamp ("2024-05-01T13:45:00Z")
+pub fn parse_since(input: &str, now: u64) -> Option<u64> {
+    let input = input.trim();
+
+    let (amount, unit) = input.split_at(input.char_indices().last()?.0);
+    if let Ok(amount) = amount.parse::<u64>() {
+        let seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86_400,
+            "w" => 7 * 86_400,
+            _ => return None,
+        };
+        return Some(now.saturating_sub(amount.saturating_mul(seconds)));
+    }
+
+    let (date, time) = match input.split_once('T') {
+        Some((date, time)) => (date, Some(time.trim_end_matches('Z'))),
+        None => (input, None),
+    };
+
+    let mut date_parts = date.split('-').map(|part| part.parse::<i64>().ok());
+    let year = date_parts.next()??;
+    let month = date_parts.next()??;
+    let day = date_parts.next()??;
+    if date_parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut secs = days_from_civil(year, month, day) * 86_400;
+    if let Some(time) = time {
+        let mut time_parts = time.split(':').map(|part| part.parse::<i64>().ok());
+        secs += time_parts.next()?? * 3600;
+        secs += time_parts.next().unwrap_or(Some(0))? * 60;
+        secs += time_parts.next().unwrap_or(Some(0))?;
+    }
+
+    u64::try_from(secs).ok()
+}
+
+// Calendar conversions from Howard Hinnant's date algorithms
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Levenshtein distance between two strings, counted in chars
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1_709_251_199), "2024-02-29T23:59:59Z");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = 1_709_251_199;
+        assert_eq!(parse_since("2h", now), Some(now - 7200));
+        assert_eq!(parse_since("7d", now), Some(now - 7 * 86_400));
+        assert_eq!(parse_since("2024-02-29", now), Some(1_709_164_800));
+        assert_eq!(parse_since("2024-02-29T23:59:59Z", now), Some(now));
+        assert_eq!(parse_since("yesterday", now), None);
+        assert_eq!(parse_since("5x", now), None);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("--port=5432"), "--port=5432");
+        assert_eq!(shell_quote("[DEV] \\d> "), "'[DEV] \\d> '");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("prod-pg", "prod-pg"), 0);
+        assert_eq!(edit_distance("prod-pg", "prod-pq"), 1);
+        assert_eq!(edit_distance("stagign", "staging"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}